@@ -0,0 +1,367 @@
+use crate::types::{ApprovalError, ApprovalRequest, ApprovalStatus, DataKey, Policy};
+use soroban_sdk::{contract, contractimpl, symbol_short, Address, BytesN, Env, String, Vec};
+
+#[contract]
+pub struct ApprovalManager;
+
+#[contractimpl]
+impl ApprovalManager {
+    pub fn initialize(env: Env, admin: Address) -> Result<(), ApprovalError> {
+        if env.storage().persistent().has(&DataKey::Admin) {
+            return Err(ApprovalError::Unauthorized);
+        }
+
+        env.storage().persistent().set(&DataKey::Admin, &admin);
+        env.storage().persistent().set(&DataKey::PolicyCount, &0u32);
+        env.storage()
+            .persistent()
+            .set(&DataKey::RequestCount, &0u64);
+
+        Ok(())
+    }
+
+    pub fn create_policy(
+        env: Env,
+        approvers: Vec<Address>,
+        threshold: u32,
+        approval_ttl: u64,
+    ) -> Result<u32, ApprovalError> {
+        Self::verify_admin(&env)?;
+        Self::validate_policy(&approvers, threshold)?;
+
+        let policy_id: u32 = env
+            .storage()
+            .persistent()
+            .get::<_, u32>(&DataKey::PolicyCount)
+            .ok_or(ApprovalError::NotInitialized)?
+            + 1;
+
+        let policy = Policy {
+            id: policy_id,
+            approvers,
+            threshold,
+            approval_ttl,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Policy(policy_id), &policy);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PolicyCount, &policy_id);
+
+        env.events()
+            .publish((symbol_short!("policy"), policy_id), threshold);
+
+        Ok(policy_id)
+    }
+
+    // Replace the approver set of an existing policy. Requests that are already
+    // open are checked against the updated policy from then on.
+    pub fn update_policy(
+        env: Env,
+        policy_id: u32,
+        approvers: Vec<Address>,
+        threshold: u32,
+        approval_ttl: u64,
+    ) -> Result<(), ApprovalError> {
+        Self::verify_admin(&env)?;
+        Self::get_policy_by_id(&env, policy_id)?;
+        Self::validate_policy(&approvers, threshold)?;
+
+        let policy = Policy {
+            id: policy_id,
+            approvers,
+            threshold,
+            approval_ttl,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Policy(policy_id), &policy);
+
+        env.events()
+            .publish((symbol_short!("policy"), policy_id), threshold);
+
+        Ok(())
+    }
+
+    // Bind a gated contract to the policy its actions must be approved under.
+    // Requests opened under a previous binding can no longer be consumed.
+    pub fn set_target_policy(
+        env: Env,
+        target: Address,
+        policy_id: u32,
+    ) -> Result<(), ApprovalError> {
+        Self::verify_admin(&env)?;
+        Self::get_policy_by_id(&env, policy_id)?;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::TargetPolicy(target.clone()), &policy_id);
+
+        env.events()
+            .publish((symbol_short!("bound"), target), policy_id);
+
+        Ok(())
+    }
+
+    pub fn propose(
+        env: Env,
+        maker: Address,
+        policy_id: u32,
+        target: Address,
+        payload: BytesN<32>,
+    ) -> Result<u64, ApprovalError> {
+        Self::check_binding(&env, &target, policy_id)?;
+        let policy = Self::get_policy_by_id(&env, policy_id)?;
+
+        // Only one live request may exist for the same action
+        if let Some(open) = Self::get_open_request(&env, &target, &payload) {
+            if matches!(
+                Self::effective_status(&env, &open),
+                ApprovalStatus::Pending | ApprovalStatus::Approved
+            ) {
+                return Err(ApprovalError::DuplicateRequest);
+            }
+        }
+
+        maker.require_auth();
+
+        let request_id: u64 = env
+            .storage()
+            .persistent()
+            .get::<_, u64>(&DataKey::RequestCount)
+            .ok_or(ApprovalError::NotInitialized)?
+            + 1;
+
+        let now = env.ledger().timestamp();
+        let request = ApprovalRequest {
+            id: request_id,
+            policy_id,
+            maker: maker.clone(),
+            target: target.clone(),
+            payload: payload.clone(),
+            approvals: Vec::new(&env),
+            status: ApprovalStatus::Pending,
+            created_at: now,
+            expires_at: now + policy.approval_ttl,
+            rejected_by: None,
+            rejection_reason: None,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Request(request_id), &request);
+        env.storage()
+            .persistent()
+            .set(&DataKey::RequestCount, &request_id);
+        env.storage()
+            .persistent()
+            .set(&DataKey::OpenRequest(target, payload), &request_id);
+
+        env.events()
+            .publish((symbol_short!("proposed"), request_id, maker), policy_id);
+
+        Ok(request_id)
+    }
+
+    pub fn approve(env: Env, approver: Address, request_id: u64) -> Result<(), ApprovalError> {
+        let mut request = Self::get_request_by_id(&env, request_id)?;
+        let policy = Self::get_policy_by_id(&env, request.policy_id)?;
+
+        Self::check_checker(&policy, &request, &approver)?;
+        match Self::effective_status(&env, &request) {
+            ApprovalStatus::Pending => {}
+            ApprovalStatus::Expired => return Err(ApprovalError::RequestExpired),
+            _ => return Err(ApprovalError::InvalidStatus),
+        }
+        if request.approvals.contains(&approver) {
+            return Err(ApprovalError::AlreadyApproved);
+        }
+
+        approver.require_auth();
+
+        request.approvals.push_back(approver.clone());
+        if request.approvals.len() >= policy.threshold {
+            request.status = ApprovalStatus::Approved;
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::Request(request_id), &request);
+
+        env.events().publish(
+            (symbol_short!("approved"), request_id, approver),
+            request.approvals.len(),
+        );
+
+        Ok(())
+    }
+
+    pub fn reject(
+        env: Env,
+        approver: Address,
+        request_id: u64,
+        reason: String,
+    ) -> Result<(), ApprovalError> {
+        let mut request = Self::get_request_by_id(&env, request_id)?;
+        let policy = Self::get_policy_by_id(&env, request.policy_id)?;
+
+        Self::check_checker(&policy, &request, &approver)?;
+        match Self::effective_status(&env, &request) {
+            ApprovalStatus::Pending | ApprovalStatus::Approved => {}
+            ApprovalStatus::Expired => return Err(ApprovalError::RequestExpired),
+            _ => return Err(ApprovalError::InvalidStatus),
+        }
+
+        approver.require_auth();
+
+        request.status = ApprovalStatus::Rejected;
+        request.rejected_by = Some(approver.clone());
+        request.rejection_reason = Some(reason.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::Request(request_id), &request);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::OpenRequest(request.target, request.payload));
+
+        env.events()
+            .publish((symbol_short!("rejected"), request_id, approver), reason);
+
+        Ok(())
+    }
+
+    // Called by a gated contract right before it runs the approved action.
+    // The approval is single use: the request is marked executed and cleared.
+    pub fn consume(env: Env, target: Address, payload: BytesN<32>) -> Result<u64, ApprovalError> {
+        let mut request = Self::get_open_request(&env, &target, &payload)
+            .ok_or(ApprovalError::RequestNotFound)?;
+
+        match Self::effective_status(&env, &request) {
+            ApprovalStatus::Approved => {}
+            ApprovalStatus::Expired => return Err(ApprovalError::RequestExpired),
+            _ => return Err(ApprovalError::NotApproved),
+        }
+        Self::check_binding(&env, &target, request.policy_id)?;
+
+        target.require_auth();
+
+        request.status = ApprovalStatus::Executed;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Request(request.id), &request);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::OpenRequest(target, payload));
+
+        env.events()
+            .publish((symbol_short!("consumed"), request.id), request.target);
+
+        Ok(request.id)
+    }
+
+    pub fn is_approved(env: Env, target: Address, payload: BytesN<32>) -> bool {
+        match Self::get_open_request(&env, &target, &payload) {
+            Some(request) => Self::effective_status(&env, &request) == ApprovalStatus::Approved,
+            None => false,
+        }
+    }
+
+    pub fn get_request(env: Env, request_id: u64) -> Result<ApprovalRequest, ApprovalError> {
+        let mut request = Self::get_request_by_id(&env, request_id)?;
+        request.status = Self::effective_status(&env, &request);
+        Ok(request)
+    }
+
+    pub fn get_policy(env: Env, policy_id: u32) -> Result<Policy, ApprovalError> {
+        Self::get_policy_by_id(&env, policy_id)
+    }
+
+    pub fn get_target_policy(env: Env, target: Address) -> Result<u32, ApprovalError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::TargetPolicy(target))
+            .ok_or(ApprovalError::TargetNotBound)
+    }
+
+    // Helper functions
+    fn verify_admin(env: &Env) -> Result<(), ApprovalError> {
+        let admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .ok_or(ApprovalError::NotInitialized)?;
+        admin.require_auth();
+        Ok(())
+    }
+
+    fn validate_policy(approvers: &Vec<Address>, threshold: u32) -> Result<(), ApprovalError> {
+        if threshold == 0 || threshold > approvers.len() {
+            return Err(ApprovalError::InvalidThreshold);
+        }
+        for (index, approver) in approvers.iter().enumerate() {
+            if approvers.first_index_of(&approver) != Some(index as u32) {
+                return Err(ApprovalError::InvalidThreshold);
+            }
+        }
+        Ok(())
+    }
+
+    // Actions on a target are only approved under the policy bound to it, so
+    // a maker cannot pick a weaker one
+    fn check_binding(env: &Env, target: &Address, policy_id: u32) -> Result<(), ApprovalError> {
+        let bound = Self::get_target_policy(env.clone(), target.clone())?;
+        if bound != policy_id {
+            return Err(ApprovalError::PolicyMismatch);
+        }
+        Ok(())
+    }
+
+    // Checkers must belong to the policy and can never be the request's maker
+    fn check_checker(
+        policy: &Policy,
+        request: &ApprovalRequest,
+        approver: &Address,
+    ) -> Result<(), ApprovalError> {
+        if !policy.approvers.contains(approver) || *approver == request.maker {
+            return Err(ApprovalError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    // Pending and approved requests lapse once their expiry time has passed
+    fn effective_status(env: &Env, request: &ApprovalRequest) -> ApprovalStatus {
+        match request.status {
+            ApprovalStatus::Pending | ApprovalStatus::Approved
+                if env.ledger().timestamp() >= request.expires_at =>
+            {
+                ApprovalStatus::Expired
+            }
+            _ => request.status.clone(),
+        }
+    }
+
+    fn get_policy_by_id(env: &Env, policy_id: u32) -> Result<Policy, ApprovalError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Policy(policy_id))
+            .ok_or(ApprovalError::PolicyNotFound)
+    }
+
+    fn get_request_by_id(env: &Env, request_id: u64) -> Result<ApprovalRequest, ApprovalError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Request(request_id))
+            .ok_or(ApprovalError::RequestNotFound)
+    }
+
+    fn get_open_request(
+        env: &Env,
+        target: &Address,
+        payload: &BytesN<32>,
+    ) -> Option<ApprovalRequest> {
+        let request_id: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::OpenRequest(target.clone(), payload.clone()))?;
+        Self::get_request_by_id(env, request_id).ok()
+    }
+}
@@ -0,0 +1,12 @@
+#![no_std]
+
+mod contract;
+mod types;
+
+pub use contract::*;
+
+#[cfg(test)]
+mod test;
+
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
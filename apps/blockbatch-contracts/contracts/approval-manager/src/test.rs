@@ -0,0 +1,317 @@
+use super::*;
+use crate::testutils::{create_approvers, create_payload};
+use crate::types::*;
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::{Address, Env, String, Vec};
+
+#[cfg(test)]
+mod test_setup {
+    use super::*;
+
+    pub fn setup_contract(
+        e: &Env,
+    ) -> (
+        ApprovalManagerClient<'_>,
+        Address,
+        Vec<Address>,
+        u32,
+        Address,
+    ) {
+        let admin = Address::generate(e);
+        let approvers = create_approvers(e, 3);
+        let target = Address::generate(e);
+
+        let contract_id = e.register(ApprovalManager, ());
+        let client = ApprovalManagerClient::new(e, &contract_id);
+
+        e.mock_all_auths();
+
+        client.initialize(&admin);
+        let policy_id = client.create_policy(&approvers, &2, &3600);
+        client.set_target_policy(&target, &policy_id);
+
+        (client, admin, approvers, policy_id, target)
+    }
+}
+
+mod test_policies {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_initialize_twice() {
+        let env = Env::default();
+        let (client, admin, _, _, _) = test_setup::setup_contract(&env);
+        client.initialize(&admin);
+    }
+
+    #[test]
+    fn test_create_and_update_policy() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, _) = test_setup::setup_contract(&env);
+
+        let policy = client.get_policy(&policy_id);
+        assert_eq!(policy.approvers, approvers);
+        assert_eq!(policy.threshold, 2);
+
+        let new_approvers = create_approvers(&env, 4);
+        client.update_policy(&policy_id, &new_approvers, &3, &7200);
+        let policy = client.get_policy(&policy_id);
+        assert_eq!(policy.approvers, new_approvers);
+        assert_eq!(policy.threshold, 3);
+        assert_eq!(policy.approval_ttl, 7200);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #2)")]
+    fn test_create_policy_threshold_too_high() {
+        let env = Env::default();
+        let (client, _, _, _, _) = test_setup::setup_contract(&env);
+        client.create_policy(&create_approvers(&env, 2), &3, &3600);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #2)")]
+    fn test_create_policy_duplicate_approver() {
+        let env = Env::default();
+        let (client, _, approvers, _, _) = test_setup::setup_contract(&env);
+        let mut duplicated = approvers.clone();
+        duplicated.push_back(approvers.get(0).unwrap());
+        client.create_policy(&duplicated, &2, &3600);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #12)")]
+    fn test_propose_under_other_policy() {
+        let env = Env::default();
+        let (client, _, approvers, _, target) = test_setup::setup_contract(&env);
+        let weak_policy = client.create_policy(&approvers, &1, &3600);
+        client.propose(
+            &Address::generate(&env),
+            &weak_policy,
+            &target,
+            &create_payload(&env, 1),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #11)")]
+    fn test_propose_for_unbound_target() {
+        let env = Env::default();
+        let (client, _, _, policy_id, _) = test_setup::setup_contract(&env);
+        client.propose(
+            &Address::generate(&env),
+            &policy_id,
+            &Address::generate(&env),
+            &create_payload(&env, 1),
+        );
+    }
+}
+
+mod test_approvals {
+    use super::*;
+
+    #[test]
+    fn test_approve_reaches_threshold() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, target) = test_setup::setup_contract(&env);
+        let maker = Address::generate(&env);
+        let payload = create_payload(&env, 1);
+
+        let request_id = client.propose(&maker, &policy_id, &target, &payload);
+        client.approve(&approvers.get(0).unwrap(), &request_id);
+        assert_eq!(
+            client.get_request(&request_id).status,
+            ApprovalStatus::Pending
+        );
+        assert!(!client.is_approved(&target, &payload));
+
+        client.approve(&approvers.get(1).unwrap(), &request_id);
+        assert_eq!(
+            client.get_request(&request_id).status,
+            ApprovalStatus::Approved
+        );
+        assert!(client.is_approved(&target, &payload));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_maker_cannot_approve() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, target) = test_setup::setup_contract(&env);
+        let maker = approvers.get(0).unwrap();
+        let request_id = client.propose(&maker, &policy_id, &target, &create_payload(&env, 1));
+        client.approve(&maker, &request_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_non_approver_cannot_approve() {
+        let env = Env::default();
+        let (client, _, _, policy_id, target) = test_setup::setup_contract(&env);
+        let request_id = client.propose(
+            &Address::generate(&env),
+            &policy_id,
+            &target,
+            &create_payload(&env, 1),
+        );
+        client.approve(&Address::generate(&env), &request_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #6)")]
+    fn test_approve_twice() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, target) = test_setup::setup_contract(&env);
+        let request_id = client.propose(
+            &Address::generate(&env),
+            &policy_id,
+            &target,
+            &create_payload(&env, 1),
+        );
+        client.approve(&approvers.get(0).unwrap(), &request_id);
+        client.approve(&approvers.get(0).unwrap(), &request_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #10)")]
+    fn test_propose_duplicate() {
+        let env = Env::default();
+        let (client, _, _, policy_id, target) = test_setup::setup_contract(&env);
+        let maker = Address::generate(&env);
+        let payload = create_payload(&env, 1);
+        client.propose(&maker, &policy_id, &target, &payload);
+        client.propose(&maker, &policy_id, &target, &payload);
+    }
+
+    #[test]
+    fn test_reject_with_reason() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, target) = test_setup::setup_contract(&env);
+        let payload = create_payload(&env, 1);
+        let request_id = client.propose(&Address::generate(&env), &policy_id, &target, &payload);
+        let reason = String::from_str(&env, "Amount does not match invoice");
+
+        client.reject(&approvers.get(2).unwrap(), &request_id, &reason);
+
+        let request = client.get_request(&request_id);
+        assert_eq!(request.status, ApprovalStatus::Rejected);
+        assert_eq!(request.rejected_by, Some(approvers.get(2).unwrap()));
+        assert_eq!(request.rejection_reason, Some(reason));
+
+        // The action can be proposed again after a rejection
+        client.propose(&Address::generate(&env), &policy_id, &target, &payload);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_approve_expired_request() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, target) = test_setup::setup_contract(&env);
+        let request_id = client.propose(
+            &Address::generate(&env),
+            &policy_id,
+            &target,
+            &create_payload(&env, 1),
+        );
+        env.ledger().with_mut(|li| {
+            li.timestamp += 3600;
+        });
+        assert_eq!(
+            client.get_request(&request_id).status,
+            ApprovalStatus::Expired
+        );
+        client.approve(&approvers.get(0).unwrap(), &request_id);
+    }
+}
+
+mod test_consume {
+    use super::*;
+
+    #[test]
+    fn test_consume_approved_request() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, target) = test_setup::setup_contract(&env);
+        let payload = create_payload(&env, 1);
+        let request_id = client.propose(&Address::generate(&env), &policy_id, &target, &payload);
+        client.approve(&approvers.get(0).unwrap(), &request_id);
+        client.approve(&approvers.get(1).unwrap(), &request_id);
+
+        assert_eq!(client.consume(&target, &payload), request_id);
+        assert_eq!(
+            client.get_request(&request_id).status,
+            ApprovalStatus::Executed
+        );
+        assert!(!client.is_approved(&target, &payload));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #4)")]
+    fn test_consume_twice() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, target) = test_setup::setup_contract(&env);
+        let payload = create_payload(&env, 1);
+        let request_id = client.propose(&Address::generate(&env), &policy_id, &target, &payload);
+        client.approve(&approvers.get(0).unwrap(), &request_id);
+        client.approve(&approvers.get(1).unwrap(), &request_id);
+        client.consume(&target, &payload);
+        client.consume(&target, &payload);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #8)")]
+    fn test_consume_pending_request() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, target) = test_setup::setup_contract(&env);
+        let payload = create_payload(&env, 1);
+        let request_id = client.propose(&Address::generate(&env), &policy_id, &target, &payload);
+        client.approve(&approvers.get(0).unwrap(), &request_id);
+        client.consume(&target, &payload);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #4)")]
+    fn test_consume_other_payload() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, target) = test_setup::setup_contract(&env);
+        let request_id = client.propose(
+            &Address::generate(&env),
+            &policy_id,
+            &target,
+            &create_payload(&env, 1),
+        );
+        client.approve(&approvers.get(0).unwrap(), &request_id);
+        client.approve(&approvers.get(1).unwrap(), &request_id);
+        client.consume(&target, &create_payload(&env, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_consume_expired_approval() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, target) = test_setup::setup_contract(&env);
+        let payload = create_payload(&env, 1);
+        let request_id = client.propose(&Address::generate(&env), &policy_id, &target, &payload);
+        client.approve(&approvers.get(0).unwrap(), &request_id);
+        client.approve(&approvers.get(1).unwrap(), &request_id);
+        env.ledger().with_mut(|li| {
+            li.timestamp += 3600;
+        });
+        client.consume(&target, &payload);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #12)")]
+    fn test_consume_after_rebinding() {
+        let env = Env::default();
+        let (client, _, approvers, policy_id, target) = test_setup::setup_contract(&env);
+        let payload = create_payload(&env, 1);
+        let request_id = client.propose(&Address::generate(&env), &policy_id, &target, &payload);
+        client.approve(&approvers.get(0).unwrap(), &request_id);
+        client.approve(&approvers.get(1).unwrap(), &request_id);
+
+        let stricter_policy = client.create_policy(&approvers, &3, &3600);
+        client.set_target_policy(&target, &stricter_policy);
+        client.consume(&target, &payload);
+    }
+}
@@ -0,0 +1,45 @@
+#![cfg(any(test, feature = "testutils"))]
+extern crate std;
+
+use crate::contract::{ApprovalManager, ApprovalManagerClient};
+use soroban_sdk::{testutils::Address as _, Address, BytesN, Env, Vec};
+
+// Helper to generate a list of approver addresses
+pub(crate) fn create_approvers(e: &Env, count: u32) -> Vec<Address> {
+    let mut approvers = Vec::new(e);
+    for _ in 0..count {
+        approvers.push_back(Address::generate(e));
+    }
+    approvers
+}
+
+// Helper to build a distinct action payload
+#[cfg(test)]
+pub(crate) fn create_payload(e: &Env, seed: u8) -> BytesN<32> {
+    BytesN::from_array(e, &[seed; 32])
+}
+
+// Helper for the contracts it gates: register an approval manager whose
+// 2-of-2 policy is bound to the gated contract
+pub fn register_approval_manager<'a>(e: &Env, target: &Address) -> ApprovalManagerClient<'a> {
+    let client = ApprovalManagerClient::new(e, &e.register(ApprovalManager, ()));
+    client.initialize(&Address::generate(e));
+    let policy_id = client.create_policy(&create_approvers(e, 2), &2, &3600);
+    client.set_target_policy(target, &policy_id);
+    client
+}
+
+// Helper to propose an action on a gated contract and have every checker of
+// the bound policy approve it
+pub fn approve_action(
+    e: &Env,
+    client: &ApprovalManagerClient,
+    target: &Address,
+    payload: &BytesN<32>,
+) {
+    let policy_id = client.get_target_policy(target);
+    let request_id = client.propose(&Address::generate(e), &policy_id, target, payload);
+    for checker in client.get_policy(&policy_id).approvers.iter() {
+        client.approve(&checker, &request_id);
+    }
+}
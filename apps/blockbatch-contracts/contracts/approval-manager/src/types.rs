@@ -0,0 +1,68 @@
+use soroban_sdk::{contracterror, contracttype, Address, BytesN, String, Vec};
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Expired,
+    Executed,
+}
+
+// A set of approvers and how many of them must sign off on a request
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Policy {
+    pub id: u32,
+    pub approvers: Vec<Address>,
+    pub threshold: u32,
+    pub approval_ttl: u64, // Seconds a request stays usable after it is proposed
+}
+
+// A maker's request to run one specific action on a gated contract. The
+// payload is a hash of the action arguments computed by the gated contract.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApprovalRequest {
+    pub id: u64,
+    pub policy_id: u32,
+    pub maker: Address,
+    pub target: Address,
+    pub payload: BytesN<32>,
+    pub approvals: Vec<Address>,
+    pub status: ApprovalStatus,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub rejected_by: Option<Address>,
+    pub rejection_reason: Option<String>,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ApprovalError {
+    Unauthorized = 1,
+    InvalidThreshold = 2,
+    PolicyNotFound = 3,
+    RequestNotFound = 4,
+    InvalidStatus = 5,
+    AlreadyApproved = 6,
+    RequestExpired = 7,
+    NotApproved = 8,
+    NotInitialized = 9,
+    DuplicateRequest = 10,
+    TargetNotBound = 11,
+    PolicyMismatch = 12,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    Admin,                            // Admin address
+    PolicyCount,                      // Number of policies created so far
+    Policy(u32),                      // Policy details by ID
+    RequestCount,                     // Number of requests proposed so far
+    Request(u64),                     // Request details by ID
+    OpenRequest(Address, BytesN<32>), // Live request ID by (target, payload)
+    TargetPolicy(Address),            // Policy governing the actions of a gated contract
+}
@@ -0,0 +1,889 @@
+use crate::types::{
+    Batch, BatchError, BatchProgress, BatchStatus, CatchUpPolicy, DataKey, FailureReason, Payment,
+    PaymentOutcome, PaymentRecord, Schedule, ScheduleStatus,
+};
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{
+    contract, contractimpl, symbol_short, token::Client, Address, Bytes, BytesN, Env, IntoVal,
+    InvokeError, Map, Symbol, TryFromVal, Val, Vec,
+};
+
+// How long the result of an idempotent call is remembered (~1 day of ledgers)
+const IDEMPOTENCY_TTL: u32 = 17_280;
+
+#[contract]
+pub struct BatchPaymentContract;
+
+#[contractimpl]
+impl BatchPaymentContract {
+    pub fn initialize(env: Env, admin: Address) -> Result<(), BatchError> {
+        if env.storage().persistent().has(&DataKey::Admin) {
+            return Err(BatchError::Unauthorized);
+        }
+
+        env.storage().persistent().set(&DataKey::Admin, &admin);
+        env.storage().persistent().set(&DataKey::BatchCount, &0u64);
+
+        Ok(())
+    }
+
+    // Require maker-checker approval before any batch starts paying out.
+    // Passing None turns the gate off again.
+    pub fn set_approval_manager(
+        env: Env,
+        caller: Address,
+        manager: Option<Address>,
+    ) -> Result<(), BatchError> {
+        let admin = Self::get_admin(&env)?;
+
+        if caller != admin {
+            return Err(BatchError::Unauthorized);
+        }
+
+        caller.require_auth();
+
+        match manager {
+            Some(manager) => env
+                .storage()
+                .persistent()
+                .set(&DataKey::ApprovalManager, &manager),
+            None => env.storage().persistent().remove(&DataKey::ApprovalManager),
+        }
+
+        Ok(())
+    }
+
+    // Value-moving calls take an optional idempotency key. A key the caller
+    // already used returns the original result instead of running again.
+    pub fn create_batch(
+        env: Env,
+        payer: Address,
+        payments: Vec<Payment>,
+        idempotency_key: Option<BytesN<32>>,
+    ) -> Result<u64, BatchError> {
+        payer.require_auth();
+
+        if let Some(batch_id) = Self::processed(&env, "create_batch", &payer, &idempotency_key) {
+            return Ok(batch_id);
+        }
+        Self::validate_payments(&payments)?;
+
+        let batch = Self::new_batch(&env, payer.clone(), payments, None, None)?;
+
+        Self::record_processed(&env, "create_batch", &payer, &idempotency_key, &batch.id);
+        Ok(batch.id)
+    }
+
+    // Create a batch paid out of a liquidity pool's liquidity rather than the
+    // payer's balance. The pool must have made this contract a payout operator
+    // and the admin must have allowed the payer to draw on that pool.
+    pub fn create_pool_batch(
+        env: Env,
+        payer: Address,
+        pool: Address,
+        payments: Vec<Payment>,
+        idempotency_key: Option<BytesN<32>>,
+    ) -> Result<u64, BatchError> {
+        payer.require_auth();
+
+        if let Some(batch_id) = Self::processed(&env, "create_pool_batch", &payer, &idempotency_key)
+        {
+            return Ok(batch_id);
+        }
+        Self::validate_payments(&payments)?;
+        if !Self::is_pool_payer(&env, &pool, &payer) {
+            return Err(BatchError::Unauthorized);
+        }
+
+        let batch = Self::new_batch(&env, payer.clone(), payments, None, Some(pool))?;
+
+        Self::record_processed(
+            &env,
+            "create_pool_batch",
+            &payer,
+            &idempotency_key,
+            &batch.id,
+        );
+        Ok(batch.id)
+    }
+
+    // Allow or disallow a payer to fund batches from a liquidity pool
+    pub fn set_pool_payer(
+        env: Env,
+        caller: Address,
+        pool: Address,
+        payer: Address,
+        allowed: bool,
+    ) -> Result<(), BatchError> {
+        let admin = Self::get_admin(&env)?;
+
+        if caller != admin {
+            return Err(BatchError::Unauthorized);
+        }
+
+        caller.require_auth();
+
+        let key = DataKey::PoolPayer(pool.clone(), payer.clone());
+        if allowed {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+
+        env.events()
+            .publish((symbol_short!("poolpayer"), pool, payer), allowed);
+
+        Ok(())
+    }
+
+    // Append more rows to a batch that has not started executing yet, so that
+    // payout lists larger than a single transaction can be uploaded in parts
+    pub fn add_payments(
+        env: Env,
+        batch_id: u64,
+        payments: Vec<Payment>,
+    ) -> Result<u32, BatchError> {
+        let mut batch = Self::get_batch_by_id(&env, batch_id)?;
+
+        // Scheduled batches are fixed by their template
+        if batch.status != BatchStatus::Created || batch.schedule_id.is_some() {
+            return Err(BatchError::InvalidStatus);
+        }
+        Self::validate_payments(&payments)?;
+
+        batch.payer.require_auth();
+
+        Self::store_payments(&env, &mut batch, &payments);
+
+        env.events()
+            .publish((symbol_short!("appended"), batch_id), batch.payment_count);
+
+        Ok(batch.payment_count)
+    }
+
+    pub fn execute_batch(
+        env: Env,
+        batch_id: u64,
+        idempotency_key: Option<BytesN<32>>,
+    ) -> Result<(), BatchError> {
+        let batch = Self::get_batch_by_id(&env, batch_id)?;
+        let payer = batch.payer.clone();
+        // Scheduled batches are pulled through the allowance and need no signature
+        if batch.schedule_id.is_none() {
+            payer.require_auth();
+        }
+        if let Some(()) = Self::processed(&env, "execute_batch", &payer, &idempotency_key) {
+            return Ok(());
+        }
+
+        let remaining = batch.payment_count - batch.cursor;
+        Self::execute_chunk(&env, batch, remaining)?;
+
+        Self::record_processed(&env, "execute_batch", &payer, &idempotency_key, &());
+        Ok(())
+    }
+
+    // Execute up to `max_items` payments starting from the stored cursor. The
+    // cursor is persisted in the same invocation as the transfers, so a failed
+    // or budget-exhausted call rolls back as a whole and can simply be retried.
+    pub fn execute_next(
+        env: Env,
+        batch_id: u64,
+        max_items: u32,
+        idempotency_key: Option<BytesN<32>>,
+    ) -> Result<BatchProgress, BatchError> {
+        if max_items == 0 {
+            return Err(BatchError::InvalidLimit);
+        }
+
+        let batch = Self::get_batch_by_id(&env, batch_id)?;
+        let payer = batch.payer.clone();
+        // Scheduled batches are pulled through the allowance and need no signature
+        if batch.schedule_id.is_none() {
+            payer.require_auth();
+        }
+        if let Some(progress) = Self::processed(&env, "execute_next", &payer, &idempotency_key) {
+            return Ok(progress);
+        }
+
+        let batch = Self::execute_chunk(&env, batch, max_items)?;
+
+        let progress = Self::progress(&batch);
+        Self::record_processed(&env, "execute_next", &payer, &idempotency_key, &progress);
+        Ok(progress)
+    }
+
+    pub fn cancel_batch(env: Env, caller: Address, batch_id: u64) -> Result<(), BatchError> {
+        let admin = Self::get_admin(&env)?;
+        let mut batch = Self::get_batch_by_id(&env, batch_id)?;
+
+        if caller != batch.payer && caller != admin {
+            return Err(BatchError::Unauthorized);
+        }
+        // A partially executed batch can still be stopped; paid rows stay paid
+        if batch.status != BatchStatus::Created && batch.status != BatchStatus::InProgress {
+            return Err(BatchError::InvalidStatus);
+        }
+
+        caller.require_auth();
+
+        batch.status = BatchStatus::Cancelled;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Batch(batch_id), &batch);
+
+        env.events()
+            .publish((symbol_short!("cancelled"), batch_id), caller);
+
+        Ok(())
+    }
+
+    pub fn get_batch(env: Env, batch_id: u64) -> Result<Batch, BatchError> {
+        Self::get_batch_by_id(&env, batch_id)
+    }
+
+    pub fn get_batch_status(env: Env, batch_id: u64) -> Result<BatchStatus, BatchError> {
+        Ok(Self::get_batch_by_id(&env, batch_id)?.status)
+    }
+
+    pub fn get_progress(env: Env, batch_id: u64) -> Result<BatchProgress, BatchError> {
+        Ok(Self::progress(&Self::get_batch_by_id(&env, batch_id)?))
+    }
+
+    // Payload the approval request for a batch has to be proposed with. It
+    // covers the rows uploaded so far, so rows added later void the approval.
+    pub fn get_approval_payload(env: Env, batch_id: u64) -> BytesN<32> {
+        Self::approval_payload(&env, batch_id)
+    }
+
+    // Page through the stored payment rows of a batch
+    pub fn get_payments(
+        env: Env,
+        batch_id: u64,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<Payment>, BatchError> {
+        let batch = Self::get_batch_by_id(&env, batch_id)?;
+        let end = start.saturating_add(limit).min(batch.payment_count);
+
+        let mut payments = Vec::new(&env);
+        for index in start..end {
+            payments.push_back(Self::get_payment(&env, batch_id, index)?);
+        }
+
+        Ok(payments)
+    }
+
+    // Attempt every failed row of a batch again. Rows that go through are
+    // marked paid; the others keep their (possibly updated) failure reason.
+    pub fn retry_failed(
+        env: Env,
+        batch_id: u64,
+        idempotency_key: Option<BytesN<32>>,
+    ) -> Result<BatchProgress, BatchError> {
+        let mut batch = Self::get_batch_by_id(&env, batch_id)?;
+
+        // Scheduled batches are pulled through the allowance, like their first run
+        if batch.schedule_id.is_none() {
+            batch.payer.require_auth();
+        }
+
+        if let Some(progress) =
+            Self::processed(&env, "retry_failed", &batch.payer, &idempotency_key)
+        {
+            return Ok(progress);
+        }
+
+        if batch.status != BatchStatus::InProgress && batch.status != BatchStatus::Completed {
+            return Err(BatchError::InvalidStatus);
+        }
+
+        let mut still_failed = Vec::new(&env);
+        for index in Self::get_failed_rows(&env, batch_id).iter() {
+            let payment = Self::get_payment(&env, batch_id, index)?;
+            if !Self::pay(&env, &batch, index, &payment) {
+                still_failed.push_back(index);
+            }
+        }
+
+        batch.failed_count = still_failed.len();
+        env.storage()
+            .persistent()
+            .set(&DataKey::FailedRows(batch_id), &still_failed);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Batch(batch_id), &batch);
+
+        env.events()
+            .publish((symbol_short!("retried"), batch_id), batch.failed_count);
+
+        let progress = Self::progress(&batch);
+        Self::record_processed(
+            &env,
+            "retry_failed",
+            &batch.payer,
+            &idempotency_key,
+            &progress,
+        );
+        Ok(progress)
+    }
+
+    // Rows of a batch whose last transfer attempt failed, with the reason
+    pub fn get_failed_payments(env: Env, batch_id: u64) -> Result<Vec<PaymentRecord>, BatchError> {
+        Self::get_batch_by_id(&env, batch_id)?;
+
+        let mut records = Vec::new(&env);
+        for index in Self::get_failed_rows(&env, batch_id).iter() {
+            records.push_back(PaymentRecord {
+                index,
+                payment: Self::get_payment(&env, batch_id, index)?,
+                outcome: Self::get_outcome(&env, batch_id, index)
+                    .unwrap_or(PaymentOutcome::Pending),
+            });
+        }
+
+        Ok(records)
+    }
+
+    pub fn get_payment_outcome(
+        env: Env,
+        batch_id: u64,
+        index: u32,
+    ) -> Result<PaymentOutcome, BatchError> {
+        let batch = Self::get_batch_by_id(&env, batch_id)?;
+        if index >= batch.payment_count {
+            return Err(BatchError::BatchNotFound);
+        }
+
+        Ok(match Self::get_outcome(&env, batch_id, index) {
+            Some(outcome) => outcome,
+            None if batch.status == BatchStatus::Cancelled => PaymentOutcome::Skipped,
+            None => PaymentOutcome::Pending,
+        })
+    }
+
+    // Store a batch template that is paid out every `interval` seconds from
+    // `start_time` on, until `end_time` if one is given
+    pub fn create_schedule(
+        env: Env,
+        payer: Address,
+        payments: Vec<Payment>,
+        start_time: u64,
+        interval: u64,
+        end_time: Option<u64>,
+        catch_up: CatchUpPolicy,
+    ) -> Result<u64, BatchError> {
+        Self::validate_payments(&payments)?;
+        if interval == 0 || end_time.is_some_and(|end| end < start_time) {
+            return Err(BatchError::InvalidSchedule);
+        }
+
+        payer.require_auth();
+
+        let schedule_id = Self::get_schedule_count(&env) + 1;
+        let schedule = Schedule {
+            id: schedule_id,
+            payer: payer.clone(),
+            payments,
+            start_time,
+            interval,
+            end_time,
+            next_due: start_time,
+            catch_up,
+            runs: 0,
+            status: ScheduleStatus::Active,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Schedule(schedule_id), &schedule);
+        env.storage()
+            .persistent()
+            .set(&DataKey::ScheduleCount, &schedule_id);
+
+        env.events()
+            .publish((symbol_short!("scheduled"), schedule_id, payer), start_time);
+
+        Ok(schedule_id)
+    }
+
+    // Pay the schedule's template once if its due time has passed. Anyone may
+    // call this; the payer's allowance is what authorises the transfers.
+    pub fn run_due(
+        env: Env,
+        schedule_id: u64,
+        idempotency_key: Option<BytesN<32>>,
+    ) -> Result<u64, BatchError> {
+        let mut schedule = Self::get_schedule_by_id(&env, schedule_id)?;
+        if let Some(batch_id) = Self::processed(&env, "run_due", &schedule.payer, &idempotency_key)
+        {
+            return Ok(batch_id);
+        }
+
+        if schedule.status != ScheduleStatus::Active {
+            return Err(BatchError::InvalidStatus);
+        }
+        let now = env.ledger().timestamp();
+        if now < schedule.next_due {
+            return Err(BatchError::NotDue);
+        }
+
+        // The approval covers the schedule as a whole and is checked on its first run
+        if schedule.runs == 0 {
+            Self::consume_approval(&env, Self::schedule_payload(&env, schedule_id))?;
+        }
+
+        let batch = Self::new_batch(
+            &env,
+            schedule.payer.clone(),
+            schedule.payments.clone(),
+            Some(schedule_id),
+            None,
+        )?;
+        let remaining = batch.payment_count;
+        let batch = Self::execute_chunk(&env, batch, remaining)?;
+
+        let periods = match schedule.catch_up {
+            CatchUpPolicy::PayMissed => 1,
+            CatchUpPolicy::SkipMissed => (now - schedule.next_due) / schedule.interval + 1,
+        };
+        schedule.next_due += periods * schedule.interval;
+        schedule.runs += 1;
+        if schedule.end_time.is_some_and(|end| schedule.next_due > end) {
+            schedule.status = ScheduleStatus::Finished;
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::Schedule(schedule_id), &schedule);
+
+        env.events().publish(
+            (symbol_short!("ran"), schedule_id, batch.id),
+            schedule.next_due,
+        );
+
+        Self::record_processed(
+            &env,
+            "run_due",
+            &schedule.payer,
+            &idempotency_key,
+            &batch.id,
+        );
+        Ok(batch.id)
+    }
+
+    pub fn cancel_schedule(env: Env, caller: Address, schedule_id: u64) -> Result<(), BatchError> {
+        let admin = Self::get_admin(&env)?;
+        let mut schedule = Self::get_schedule_by_id(&env, schedule_id)?;
+
+        if caller != schedule.payer && caller != admin {
+            return Err(BatchError::Unauthorized);
+        }
+        if schedule.status != ScheduleStatus::Active {
+            return Err(BatchError::InvalidStatus);
+        }
+
+        caller.require_auth();
+
+        schedule.status = ScheduleStatus::Cancelled;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Schedule(schedule_id), &schedule);
+
+        env.events()
+            .publish((symbol_short!("unsched"), schedule_id), caller);
+
+        Ok(())
+    }
+
+    pub fn get_schedule(env: Env, schedule_id: u64) -> Result<Schedule, BatchError> {
+        Self::get_schedule_by_id(&env, schedule_id)
+    }
+
+    // Payload the approval request for a schedule has to be proposed with
+    pub fn get_schedule_approval_payload(env: Env, schedule_id: u64) -> BytesN<32> {
+        Self::schedule_payload(&env, schedule_id)
+    }
+
+    // Helper functions
+    fn new_batch(
+        env: &Env,
+        payer: Address,
+        payments: Vec<Payment>,
+        schedule_id: Option<u64>,
+        pool: Option<Address>,
+    ) -> Result<Batch, BatchError> {
+        let batch_id = Self::get_batch_count(env)? + 1;
+        let mut batch = Batch {
+            id: batch_id,
+            payer: payer.clone(),
+            payment_count: 0,
+            cursor: 0,
+            status: BatchStatus::Created,
+            created_at: env.ledger().timestamp(),
+            schedule_id,
+            failed_count: 0,
+            pool,
+        };
+        Self::store_payments(env, &mut batch, &payments);
+        env.storage()
+            .persistent()
+            .set(&DataKey::BatchCount, &batch_id);
+
+        env.events().publish(
+            (symbol_short!("created"), batch_id, payer),
+            batch.payment_count,
+        );
+
+        Ok(batch)
+    }
+
+    // The caller has already required the payer's signature for batches that
+    // need one. Scheduled batches are pulled through the payer's allowance and
+    // were approved together with their schedule, so they skip the approval.
+    fn execute_chunk(env: &Env, mut batch: Batch, max_items: u32) -> Result<Batch, BatchError> {
+        if batch.status != BatchStatus::Created && batch.status != BatchStatus::InProgress {
+            return Err(BatchError::InvalidStatus);
+        }
+
+        // The approval is checked once, when the first chunk runs
+        let pull = batch.schedule_id.is_some();
+        if !pull && batch.status == BatchStatus::Created {
+            Self::consume_approval(env, Self::approval_payload(env, batch.id))?;
+        }
+
+        let end = batch
+            .cursor
+            .saturating_add(max_items)
+            .min(batch.payment_count);
+
+        let mut chunk = Vec::new(env);
+        for index in batch.cursor..end {
+            chunk.push_back(Self::get_payment(env, batch.id, index)?);
+        }
+
+        // The payer must cover every token in the chunk before anything moves.
+        // Pool-funded batches are checked against the pool's limits per row.
+        let totals = if batch.pool.is_none() {
+            Self::totals_by_token(env, &chunk)
+        } else {
+            Map::new(env)
+        };
+        for (token, total) in totals.iter() {
+            let token_client = Client::new(env, &token);
+            if token_client.balance(&batch.payer) < total {
+                return Err(BatchError::InsufficientBalance);
+            }
+            if pull && token_client.allowance(&batch.payer, &env.current_contract_address()) < total
+            {
+                return Err(BatchError::InsufficientAllowance);
+            }
+        }
+
+        // A row the token rejects is recorded as failed instead of aborting the
+        // whole chunk, so one bad recipient cannot block the rest of the batch
+        let mut failed_rows = Self::get_failed_rows(env, batch.id);
+        for (offset, payment) in chunk.iter().enumerate() {
+            let index = batch.cursor + offset as u32;
+            if !Self::pay(env, &batch, index, &payment) {
+                failed_rows.push_back(index);
+            }
+        }
+        batch.failed_count = failed_rows.len();
+        env.storage()
+            .persistent()
+            .set(&DataKey::FailedRows(batch.id), &failed_rows);
+
+        batch.cursor = end;
+        batch.status = if batch.cursor == batch.payment_count {
+            BatchStatus::Completed
+        } else {
+            BatchStatus::InProgress
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Batch(batch.id), &batch);
+
+        if batch.status == BatchStatus::Completed {
+            env.events()
+                .publish((symbol_short!("executed"), batch.id), batch.payment_count);
+        } else {
+            env.events().publish(
+                (symbol_short!("progress"), batch.id),
+                (batch.cursor, batch.payment_count),
+            );
+        }
+
+        Ok(batch)
+    }
+
+    // Transfer a single row and record its outcome. Returns false if the token
+    // rejected the transfer; the error is kept as the row's failure reason.
+    fn pay(env: &Env, batch: &Batch, index: u32, payment: &Payment) -> bool {
+        let result = if let Some(pool) = &batch.pool {
+            Self::pool_payout(env, pool, payment)
+        } else {
+            let token_client = Client::new(env, &payment.token);
+            let result = if batch.schedule_id.is_some() {
+                token_client.try_transfer_from(
+                    &env.current_contract_address(),
+                    &batch.payer,
+                    &payment.recipient,
+                    &payment.amount,
+                )
+            } else {
+                token_client.try_transfer(&batch.payer, &payment.recipient, &payment.amount)
+            };
+            result.map(|_| ()).map_err(Self::failure_reason)
+        };
+
+        let outcome = match result {
+            Ok(()) => {
+                env.events().publish(
+                    (symbol_short!("paid"), batch.id, payment.recipient.clone()),
+                    (payment.token.clone(), payment.amount),
+                );
+                PaymentOutcome::Paid
+            }
+            Err(reason) => {
+                env.events().publish(
+                    (symbol_short!("failed"), batch.id, payment.recipient.clone()),
+                    (payment.token.clone(), payment.amount, reason.clone()),
+                );
+                PaymentOutcome::Failed(reason)
+            }
+        };
+
+        let paid = outcome == PaymentOutcome::Paid;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Outcome(batch.id, index), &outcome);
+        paid
+    }
+
+    // Have the pool pay the row out of its liquidity, with this contract acting
+    // as the pool's payout operator
+    fn pool_payout(env: &Env, pool: &Address, payment: &Payment) -> Result<(), FailureReason> {
+        let result = env.try_invoke_contract::<(), soroban_sdk::Error>(
+            pool,
+            &Symbol::new(env, "operator_payout"),
+            Vec::from_array(
+                env,
+                [
+                    env.current_contract_address().into_val(env),
+                    payment.token.into_val(env),
+                    payment.recipient.into_val(env),
+                    payment.amount.into_val(env),
+                    None::<BytesN<32>>.into_val(env),
+                ],
+            ),
+        );
+        match result {
+            Ok(_) => Ok(()),
+            Err(Ok(error)) => match InvokeError::from(error) {
+                InvokeError::Contract(code) => Err(FailureReason::PoolRejected(code)),
+                InvokeError::Abort => Err(FailureReason::Aborted),
+            },
+            Err(Err(_)) => Err(FailureReason::Aborted),
+        }
+    }
+
+    // Map the Stellar Asset Contract error codes onto failure reasons
+    fn failure_reason(error: Result<soroban_sdk::Error, InvokeError>) -> FailureReason {
+        let error = match error {
+            Ok(error) => InvokeError::from(error),
+            Err(error) => error,
+        };
+        match error {
+            InvokeError::Contract(9) => FailureReason::InsufficientAllowance,
+            InvokeError::Contract(10) => FailureReason::InsufficientBalance,
+            InvokeError::Contract(11) => FailureReason::Deauthorized,
+            InvokeError::Contract(13) => FailureReason::MissingTrustline,
+            InvokeError::Contract(code) => FailureReason::TokenError(code),
+            InvokeError::Abort => FailureReason::Aborted,
+        }
+    }
+
+    fn validate_payments(payments: &Vec<Payment>) -> Result<(), BatchError> {
+        if payments.is_empty() {
+            return Err(BatchError::EmptyBatch);
+        }
+        if payments.iter().any(|p| p.amount <= 0) {
+            return Err(BatchError::InvalidAmount);
+        }
+        Ok(())
+    }
+
+    // Rows are chained into the batch's digest as they are stored
+    fn store_payments(env: &Env, batch: &mut Batch, payments: &Vec<Payment>) {
+        let mut digest = Self::get_rows_digest(env, batch.id);
+        for payment in payments.iter() {
+            let mut row = Bytes::from_array(env, &digest.to_array());
+            row.append(&payment.clone().to_xdr(env));
+            digest = env.crypto().sha256(&row).to_bytes();
+
+            env.storage()
+                .persistent()
+                .set(&DataKey::Payment(batch.id, batch.payment_count), &payment);
+            batch.payment_count += 1;
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::RowsDigest(batch.id), &digest);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Batch(batch.id), batch);
+    }
+
+    fn approval_payload(env: &Env, batch_id: u64) -> BytesN<32> {
+        let action = (
+            Symbol::new(env, "execute_batch"),
+            batch_id,
+            Self::get_rows_digest(env, batch_id),
+        );
+        env.crypto().sha256(&action.to_xdr(env)).to_bytes()
+    }
+
+    fn consume_approval(env: &Env, payload: BytesN<32>) -> Result<(), BatchError> {
+        let manager: Option<Address> = env.storage().persistent().get(&DataKey::ApprovalManager);
+        if let Some(manager) = manager {
+            let result = env.try_invoke_contract::<u64, soroban_sdk::Error>(
+                &manager,
+                &Symbol::new(env, "consume"),
+                Vec::from_array(
+                    env,
+                    [
+                        env.current_contract_address().into_val(env),
+                        payload.into_val(env),
+                    ],
+                ),
+            );
+            if !matches!(result, Ok(Ok(_))) {
+                return Err(BatchError::ApprovalRequired);
+            }
+        }
+        Ok(())
+    }
+
+    fn schedule_payload(env: &Env, schedule_id: u64) -> BytesN<32> {
+        let action = (Symbol::new(env, "run_schedule"), schedule_id);
+        env.crypto().sha256(&action.to_xdr(env)).to_bytes()
+    }
+
+    // Result of an earlier call made by `caller` with the same idempotency key
+    fn processed<T: TryFromVal<Env, Val>>(
+        env: &Env,
+        function: &str,
+        caller: &Address,
+        idempotency_key: &Option<BytesN<32>>,
+    ) -> Option<T> {
+        let key = idempotency_key.as_ref()?;
+        env.storage().temporary().get(&DataKey::Processed(
+            Symbol::new(env, function),
+            caller.clone(),
+            key.clone(),
+        ))
+    }
+
+    // Remember the result of a call until the key's TTL runs out
+    fn record_processed<T: IntoVal<Env, Val>>(
+        env: &Env,
+        function: &str,
+        caller: &Address,
+        idempotency_key: &Option<BytesN<32>>,
+        result: &T,
+    ) {
+        if let Some(key) = idempotency_key {
+            let key = DataKey::Processed(Symbol::new(env, function), caller.clone(), key.clone());
+            env.storage().temporary().set(&key, result);
+            env.storage()
+                .temporary()
+                .extend_ttl(&key, IDEMPOTENCY_TTL, IDEMPOTENCY_TTL);
+        }
+    }
+
+    fn get_schedule_count(env: &Env) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ScheduleCount)
+            .unwrap_or(0)
+    }
+
+    fn get_schedule_by_id(env: &Env, schedule_id: u64) -> Result<Schedule, BatchError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Schedule(schedule_id))
+            .ok_or(BatchError::ScheduleNotFound)
+    }
+
+    fn progress(batch: &Batch) -> BatchProgress {
+        BatchProgress {
+            batch_id: batch.id,
+            processed: batch.cursor,
+            total: batch.payment_count,
+            failed: batch.failed_count,
+            status: batch.status.clone(),
+        }
+    }
+
+    fn get_admin(env: &Env) -> Result<Address, BatchError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .ok_or(BatchError::NotInitialized)
+    }
+
+    fn get_batch_count(env: &Env) -> Result<u64, BatchError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::BatchCount)
+            .ok_or(BatchError::NotInitialized)
+    }
+
+    fn get_batch_by_id(env: &Env, batch_id: u64) -> Result<Batch, BatchError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Batch(batch_id))
+            .ok_or(BatchError::BatchNotFound)
+    }
+
+    fn get_payment(env: &Env, batch_id: u64, index: u32) -> Result<Payment, BatchError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Payment(batch_id, index))
+            .ok_or(BatchError::BatchNotFound)
+    }
+
+    fn is_pool_payer(env: &Env, pool: &Address, payer: &Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::PoolPayer(pool.clone(), payer.clone()))
+    }
+
+    fn get_outcome(env: &Env, batch_id: u64, index: u32) -> Option<PaymentOutcome> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Outcome(batch_id, index))
+    }
+
+    fn get_rows_digest(env: &Env, batch_id: u64) -> BytesN<32> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RowsDigest(batch_id))
+            .unwrap_or(BytesN::from_array(env, &[0; 32]))
+    }
+
+    fn get_failed_rows(env: &Env, batch_id: u64) -> Vec<u32> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::FailedRows(batch_id))
+            .unwrap_or(Vec::new(env))
+    }
+
+    fn totals_by_token(env: &Env, payments: &Vec<Payment>) -> Map<Address, i128> {
+        let mut totals: Map<Address, i128> = Map::new(env);
+        for payment in payments.iter() {
+            let current = totals.get(payment.token.clone()).unwrap_or(0);
+            totals.set(payment.token, current + payment.amount);
+        }
+        totals
+    }
+}
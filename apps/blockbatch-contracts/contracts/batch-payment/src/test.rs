@@ -0,0 +1,1014 @@
+use super::*;
+use crate::testutils::{check_balance, create_payment, create_token_contract, mint_tokens};
+use crate::types::*;
+use approval_manager::testutils::{approve_action, register_approval_manager};
+use approval_manager::ApprovalManagerClient;
+use soroban_sdk::testutils::{Address as _, Events};
+use soroban_sdk::{vec, Address, Env, Vec};
+
+#[cfg(test)]
+mod test_setup {
+    use super::*;
+
+    pub fn setup_contract(
+        e: &Env,
+    ) -> (
+        BatchPaymentContractClient<'_>,
+        Address,
+        Address,
+        Address,
+        Address,
+    ) {
+        let admin = Address::generate(e);
+        let payer = Address::generate(e);
+
+        let (token1_address, token1_admin) = create_token_contract(e, &admin);
+        let (token2_address, token2_admin) = create_token_contract(e, &admin);
+
+        let contract_id = e.register(BatchPaymentContract, ());
+        let client = BatchPaymentContractClient::new(e, &contract_id);
+
+        e.mock_all_auths();
+
+        mint_tokens(&token1_admin, &payer, &10_000);
+        mint_tokens(&token2_admin, &payer, &10_000);
+
+        client.initialize(&admin);
+
+        (client, admin, payer, token1_address, token2_address)
+    }
+
+    pub fn payments(e: &Env, token1: &Address, token2: &Address) -> Vec<Payment> {
+        vec![
+            e,
+            create_payment(&Address::generate(e), token1, 1_000),
+            create_payment(&Address::generate(e), token1, 2_000),
+            create_payment(&Address::generate(e), token2, 500),
+        ]
+    }
+}
+
+mod test_initialization {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_initialize_twice() {
+        let env = Env::default();
+        let (client, admin, _, _, _) = test_setup::setup_contract(&env);
+        client.initialize(&admin);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_create_batch_not_initialized() {
+        let env = Env::default();
+        let contract_id = env.register(BatchPaymentContract, ());
+        let client = BatchPaymentContractClient::new(&env, &contract_id);
+        let (token_address, _) = create_token_contract(&env, &Address::generate(&env));
+
+        env.mock_all_auths();
+
+        let payments = vec![
+            &env,
+            create_payment(&Address::generate(&env), &token_address, 100),
+        ];
+        client.create_batch(&Address::generate(&env), &payments, &None);
+    }
+}
+
+mod test_create {
+    use super::*;
+
+    #[test]
+    fn test_create_batch_success() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let payments = test_setup::payments(&env, &token1, &token2);
+
+        let first = client.create_batch(&payer, &payments, &None);
+        let second = client.create_batch(&payer, &payments, &None);
+        assert_eq!(first, 1);
+        assert_eq!(second, 2);
+
+        let batch = client.get_batch(&first);
+        assert_eq!(batch.payer, payer);
+        assert_eq!(batch.payment_count, 3);
+        assert_eq!(batch.status, BatchStatus::Created);
+        assert_eq!(client.get_payments(&first, &0, &10), payments);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #4)")]
+    fn test_create_batch_empty() {
+        let env = Env::default();
+        let (client, _, payer, _, _) = test_setup::setup_contract(&env);
+        client.create_batch(&payer, &Vec::new(&env), &None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #2)")]
+    fn test_create_batch_invalid_amount() {
+        let env = Env::default();
+        let (client, _, payer, token1, _) = test_setup::setup_contract(&env);
+        let payments = vec![
+            &env,
+            create_payment(&Address::generate(&env), &token1, 100),
+            create_payment(&Address::generate(&env), &token1, 0),
+        ];
+        client.create_batch(&payer, &payments, &None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #6)")]
+    fn test_get_batch_not_found() {
+        let env = Env::default();
+        let (client, _, _, _, _) = test_setup::setup_contract(&env);
+        client.get_batch(&1);
+    }
+}
+
+mod test_execute {
+    use super::*;
+
+    #[test]
+    fn test_execute_batch_success() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let payments = test_setup::payments(&env, &token1, &token2);
+        let batch_id = client.create_batch(&payer, &payments, &None);
+
+        client.execute_batch(&batch_id, &None);
+
+        assert_eq!(client.get_batch_status(&batch_id), BatchStatus::Completed);
+        for payment in payments.iter() {
+            assert_eq!(
+                check_balance(&env, &payment.token, &payment.recipient),
+                payment.amount
+            );
+        }
+        assert_eq!(check_balance(&env, &token1, &payer), 7_000);
+        assert_eq!(check_balance(&env, &token2, &payer), 9_500);
+    }
+
+    #[test]
+    fn test_execute_batch_events() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let payments = test_setup::payments(&env, &token1, &token2);
+        let batch_id = client.create_batch(&payer, &payments, &None);
+
+        client.execute_batch(&batch_id, &None);
+
+        // Three token transfers, three payment events and the execution event
+        assert_eq!(env.events().all().len(), 7);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #5)")]
+    fn test_execute_batch_insufficient_balance() {
+        let env = Env::default();
+        let (client, _, payer, token1, _) = test_setup::setup_contract(&env);
+        let payments = vec![
+            &env,
+            create_payment(&Address::generate(&env), &token1, 6_000),
+            create_payment(&Address::generate(&env), &token1, 6_000),
+        ];
+        let batch_id = client.create_batch(&payer, &payments, &None);
+        client.execute_batch(&batch_id, &None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_execute_batch_twice() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.execute_batch(&batch_id, &None);
+        client.execute_batch(&batch_id, &None);
+    }
+}
+
+mod test_chunked {
+    use super::*;
+
+    #[test]
+    fn test_execute_next_in_chunks() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let payments = test_setup::payments(&env, &token1, &token2);
+        let batch_id = client.create_batch(&payer, &payments, &None);
+
+        let progress = client.execute_next(&batch_id, &2, &None);
+        assert_eq!(progress.processed, 2);
+        assert_eq!(progress.total, 3);
+        assert_eq!(progress.status, BatchStatus::InProgress);
+        assert_eq!(check_balance(&env, &token1, &payer), 7_000);
+        assert_eq!(check_balance(&env, &token2, &payer), 10_000);
+
+        let progress = client.execute_next(&batch_id, &2, &None);
+        assert_eq!(progress.processed, 3);
+        assert_eq!(progress.status, BatchStatus::Completed);
+        assert_eq!(check_balance(&env, &token2, &payer), 9_500);
+        assert_eq!(client.get_progress(&batch_id), progress);
+    }
+
+    #[test]
+    fn test_execute_batch_resumes_from_cursor() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let payments = test_setup::payments(&env, &token1, &token2);
+        let batch_id = client.create_batch(&payer, &payments, &None);
+
+        client.execute_next(&batch_id, &1, &None);
+        client.execute_batch(&batch_id, &None);
+
+        // Every recipient is paid exactly once
+        for payment in payments.iter() {
+            assert_eq!(
+                check_balance(&env, &payment.token, &payment.recipient),
+                payment.amount
+            );
+        }
+        assert_eq!(client.get_batch_status(&batch_id), BatchStatus::Completed);
+    }
+
+    #[test]
+    fn test_add_payments() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+
+        let extra = test_setup::payments(&env, &token1, &token2);
+        assert_eq!(client.add_payments(&batch_id, &extra), 6);
+        assert_eq!(client.get_payments(&batch_id, &3, &3), extra);
+
+        client.execute_batch(&batch_id, &None);
+        assert_eq!(check_balance(&env, &token1, &payer), 4_000);
+        assert_eq!(check_balance(&env, &token2, &payer), 9_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_add_payments_after_start() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.execute_next(&batch_id, &1, &None);
+        client.add_payments(&batch_id, &test_setup::payments(&env, &token1, &token2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #8)")]
+    fn test_execute_next_zero_limit() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.execute_next(&batch_id, &0, &None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_execute_next_after_completion() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.execute_next(&batch_id, &3, &None);
+        client.execute_next(&batch_id, &1, &None);
+    }
+
+    #[test]
+    fn test_cancel_in_progress_batch() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.execute_next(&batch_id, &1, &None);
+        client.cancel_batch(&payer, &batch_id);
+
+        assert_eq!(client.get_batch_status(&batch_id), BatchStatus::Cancelled);
+        assert_eq!(check_balance(&env, &token1, &payer), 9_000);
+    }
+}
+
+mod test_approval {
+    use super::*;
+
+    fn setup_gate<'a>(
+        env: &Env,
+        client: &BatchPaymentContractClient,
+        admin: &Address,
+    ) -> ApprovalManagerClient<'a> {
+        let manager = register_approval_manager(env, &client.address);
+        client.set_approval_manager(admin, &Some(manager.address.clone()));
+        manager
+    }
+
+    #[test]
+    fn test_execute_approved_batch() {
+        let env = Env::default();
+        let (client, admin, payer, token1, token2) = test_setup::setup_contract(&env);
+        let manager = setup_gate(&env, &client, &admin);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+
+        approve_action(
+            &env,
+            &manager,
+            &client.address,
+            &client.get_approval_payload(&batch_id),
+        );
+        client.execute_next(&batch_id, &1, &None);
+        // Later chunks do not need a fresh approval
+        client.execute_next(&batch_id, &2, &None);
+
+        assert_eq!(client.get_batch_status(&batch_id), BatchStatus::Completed);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #9)")]
+    fn test_execute_unapproved_batch() {
+        let env = Env::default();
+        let (client, admin, payer, token1, token2) = test_setup::setup_contract(&env);
+        setup_gate(&env, &client, &admin);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.execute_batch(&batch_id, &None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #9)")]
+    fn test_approval_is_bound_to_batch() {
+        let env = Env::default();
+        let (client, admin, payer, token1, token2) = test_setup::setup_contract(&env);
+        let manager = setup_gate(&env, &client, &admin);
+        let approved =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        let other =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+
+        approve_action(
+            &env,
+            &manager,
+            &client.address,
+            &client.get_approval_payload(&approved),
+        );
+        client.execute_batch(&other, &None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #9)")]
+    fn test_rows_added_after_approval() {
+        let env = Env::default();
+        let (client, admin, payer, token1, token2) = test_setup::setup_contract(&env);
+        let manager = setup_gate(&env, &client, &admin);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+
+        approve_action(
+            &env,
+            &manager,
+            &client.address,
+            &client.get_approval_payload(&batch_id),
+        );
+        let recipient = Address::generate(&env);
+        client.add_payments(
+            &batch_id,
+            &vec![&env, create_payment(&recipient, &token1, 500)],
+        );
+        client.execute_batch(&batch_id, &None);
+    }
+
+    #[test]
+    fn test_remove_approval_manager() {
+        let env = Env::default();
+        let (client, admin, payer, token1, token2) = test_setup::setup_contract(&env);
+        setup_gate(&env, &client, &admin);
+        client.set_approval_manager(&admin, &None);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.execute_batch(&batch_id, &None);
+        assert_eq!(client.get_batch_status(&batch_id), BatchStatus::Completed);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_set_approval_manager_unauthorized() {
+        let env = Env::default();
+        let (client, _, payer, _, _) = test_setup::setup_contract(&env);
+        client.set_approval_manager(&payer, &Some(Address::generate(&env)));
+    }
+}
+
+mod test_cancel {
+    use super::*;
+
+    #[test]
+    fn test_cancel_batch_by_payer() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.cancel_batch(&payer, &batch_id);
+        assert_eq!(client.get_batch_status(&batch_id), BatchStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_cancel_batch_by_admin() {
+        let env = Env::default();
+        let (client, admin, payer, token1, token2) = test_setup::setup_contract(&env);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.cancel_batch(&admin, &batch_id);
+        assert_eq!(client.get_batch_status(&batch_id), BatchStatus::Cancelled);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_cancel_batch_unauthorized() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.cancel_batch(&Address::generate(&env), &batch_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_execute_cancelled_batch() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.cancel_batch(&payer, &batch_id);
+        client.execute_batch(&batch_id, &None);
+    }
+}
+
+mod test_schedule {
+    use super::*;
+    use soroban_sdk::testutils::Ledger;
+    use soroban_sdk::token::TokenClient;
+
+    const START: u64 = 1_000;
+    const INTERVAL: u64 = 100;
+
+    fn create_schedule(
+        env: &Env,
+        client: &BatchPaymentContractClient,
+        payer: &Address,
+        token: &Address,
+        end_time: Option<u64>,
+        catch_up: CatchUpPolicy,
+    ) -> (u64, Address) {
+        let recipient = Address::generate(env);
+        TokenClient::new(env, token).approve(payer, &client.address, &10_000, &1_000);
+        let schedule_id = client.create_schedule(
+            payer,
+            &vec![env, create_payment(&recipient, token, 100)],
+            &START,
+            &INTERVAL,
+            &end_time,
+            &catch_up,
+        );
+        (schedule_id, recipient)
+    }
+
+    fn set_time(env: &Env, timestamp: u64) {
+        env.ledger().with_mut(|li| {
+            li.timestamp = timestamp;
+        });
+    }
+
+    #[test]
+    fn test_run_due_pays_template() {
+        let env = Env::default();
+        let (client, _, payer, token1, _) = test_setup::setup_contract(&env);
+        let (schedule_id, recipient) = create_schedule(
+            &env,
+            &client,
+            &payer,
+            &token1,
+            None,
+            CatchUpPolicy::SkipMissed,
+        );
+
+        set_time(&env, START);
+        let batch_id = client.run_due(&schedule_id, &None);
+
+        assert_eq!(check_balance(&env, &token1, &recipient), 100);
+        let batch = client.get_batch(&batch_id);
+        assert_eq!(batch.status, BatchStatus::Completed);
+        assert_eq!(batch.schedule_id, Some(schedule_id));
+
+        let schedule = client.get_schedule(&schedule_id);
+        assert_eq!(schedule.next_due, START + INTERVAL);
+        assert_eq!(schedule.runs, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #11)")]
+    fn test_run_before_due() {
+        let env = Env::default();
+        let (client, _, payer, token1, _) = test_setup::setup_contract(&env);
+        let (schedule_id, _) = create_schedule(
+            &env,
+            &client,
+            &payer,
+            &token1,
+            None,
+            CatchUpPolicy::SkipMissed,
+        );
+
+        set_time(&env, START);
+        client.run_due(&schedule_id, &None);
+        // The next period has not started yet
+        set_time(&env, START + INTERVAL - 1);
+        client.run_due(&schedule_id, &None);
+    }
+
+    #[test]
+    fn test_skip_missed_periods() {
+        let env = Env::default();
+        let (client, _, payer, token1, _) = test_setup::setup_contract(&env);
+        let (schedule_id, recipient) = create_schedule(
+            &env,
+            &client,
+            &payer,
+            &token1,
+            None,
+            CatchUpPolicy::SkipMissed,
+        );
+
+        set_time(&env, START + 3 * INTERVAL + 50);
+        client.run_due(&schedule_id, &None);
+
+        assert_eq!(check_balance(&env, &token1, &recipient), 100);
+        assert_eq!(
+            client.get_schedule(&schedule_id).next_due,
+            START + 4 * INTERVAL
+        );
+        assert!(client.try_run_due(&schedule_id, &None).is_err());
+    }
+
+    #[test]
+    fn test_pay_missed_periods() {
+        let env = Env::default();
+        let (client, _, payer, token1, _) = test_setup::setup_contract(&env);
+        let (schedule_id, recipient) = create_schedule(
+            &env,
+            &client,
+            &payer,
+            &token1,
+            None,
+            CatchUpPolicy::PayMissed,
+        );
+
+        set_time(&env, START + 2 * INTERVAL + 50);
+        client.run_due(&schedule_id, &None);
+        client.run_due(&schedule_id, &None);
+        client.run_due(&schedule_id, &None);
+
+        // Three periods were due, a fourth is not
+        assert_eq!(check_balance(&env, &token1, &recipient), 300);
+        assert_eq!(
+            client.get_schedule(&schedule_id).next_due,
+            START + 3 * INTERVAL
+        );
+        assert!(client.try_run_due(&schedule_id, &None).is_err());
+    }
+
+    #[test]
+    fn test_schedule_finishes_at_end_time() {
+        let env = Env::default();
+        let (client, _, payer, token1, _) = test_setup::setup_contract(&env);
+        let (schedule_id, recipient) = create_schedule(
+            &env,
+            &client,
+            &payer,
+            &token1,
+            Some(START + INTERVAL),
+            CatchUpPolicy::PayMissed,
+        );
+
+        set_time(&env, START + 5 * INTERVAL);
+        client.run_due(&schedule_id, &None);
+        client.run_due(&schedule_id, &None);
+
+        let schedule = client.get_schedule(&schedule_id);
+        assert_eq!(schedule.status, ScheduleStatus::Finished);
+        assert_eq!(schedule.runs, 2);
+        assert_eq!(check_balance(&env, &token1, &recipient), 200);
+        assert!(client.try_run_due(&schedule_id, &None).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_run_cancelled_schedule() {
+        let env = Env::default();
+        let (client, _, payer, token1, _) = test_setup::setup_contract(&env);
+        let (schedule_id, _) = create_schedule(
+            &env,
+            &client,
+            &payer,
+            &token1,
+            None,
+            CatchUpPolicy::SkipMissed,
+        );
+
+        client.cancel_schedule(&payer, &schedule_id);
+        assert_eq!(
+            client.get_schedule(&schedule_id).status,
+            ScheduleStatus::Cancelled
+        );
+        set_time(&env, START);
+        client.run_due(&schedule_id, &None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #13)")]
+    fn test_run_without_allowance() {
+        let env = Env::default();
+        let (client, _, payer, token1, _) = test_setup::setup_contract(&env);
+        let (schedule_id, _) = create_schedule(
+            &env,
+            &client,
+            &payer,
+            &token1,
+            None,
+            CatchUpPolicy::SkipMissed,
+        );
+
+        TokenClient::new(&env, &token1).approve(&payer, &client.address, &0, &1_000);
+        set_time(&env, START);
+        client.run_due(&schedule_id, &None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #12)")]
+    fn test_create_schedule_zero_interval() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        client.create_schedule(
+            &payer,
+            &test_setup::payments(&env, &token1, &token2),
+            &START,
+            &0,
+            &None,
+            &CatchUpPolicy::SkipMissed,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #9)")]
+    fn test_run_unapproved_schedule() {
+        let env = Env::default();
+        let (client, admin, payer, token1, _) = test_setup::setup_contract(&env);
+        let manager = register_approval_manager(&env, &client.address);
+        client.set_approval_manager(&admin, &Some(manager.address));
+        let (schedule_id, _) = create_schedule(
+            &env,
+            &client,
+            &payer,
+            &token1,
+            None,
+            CatchUpPolicy::SkipMissed,
+        );
+
+        set_time(&env, START);
+        client.run_due(&schedule_id, &None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_scheduled_batch_is_fixed() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let (schedule_id, _) = create_schedule(
+            &env,
+            &client,
+            &payer,
+            &token1,
+            None,
+            CatchUpPolicy::SkipMissed,
+        );
+
+        set_time(&env, START);
+        let batch_id = client.run_due(&schedule_id, &None);
+        client.add_payments(&batch_id, &test_setup::payments(&env, &token1, &token2));
+    }
+}
+
+mod test_outcomes {
+    use super::*;
+    use crate::testutils::create_revocable_token_contract;
+    use soroban_sdk::token::StellarAssetClient;
+
+    // Three rows of a revocable token; the middle recipient is frozen
+    fn setup_frozen_batch<'a>(
+        env: &Env,
+        client: &BatchPaymentContractClient,
+        admin: &Address,
+        payer: &Address,
+    ) -> (u64, Address, Address, StellarAssetClient<'a>) {
+        let (token, token_admin) = create_revocable_token_contract(env, admin);
+        mint_tokens(&token_admin, payer, &10_000);
+
+        let frozen = Address::generate(env);
+        token_admin.set_authorized(&frozen, &false);
+        let batch_id = client.create_batch(
+            payer,
+            &vec![
+                env,
+                create_payment(&Address::generate(env), &token, 1_000),
+                create_payment(&frozen, &token, 2_000),
+                create_payment(&Address::generate(env), &token, 500),
+            ],
+            &None,
+        );
+        (batch_id, frozen, token, token_admin)
+    }
+
+    #[test]
+    fn test_failed_row_does_not_block_batch() {
+        let env = Env::default();
+        let (client, admin, payer, _, _) = test_setup::setup_contract(&env);
+        let (batch_id, frozen, token1, _) = setup_frozen_batch(&env, &client, &admin, &payer);
+
+        client.execute_batch(&batch_id, &None);
+
+        let progress = client.get_progress(&batch_id);
+        assert_eq!(progress.status, BatchStatus::Completed);
+        assert_eq!(progress.failed, 1);
+        assert_eq!(check_balance(&env, &token1, &payer), 8_500);
+        assert_eq!(check_balance(&env, &token1, &frozen), 0);
+
+        assert_eq!(
+            client.get_payment_outcome(&batch_id, &0),
+            PaymentOutcome::Paid
+        );
+        let failed = client.get_failed_payments(&batch_id);
+        assert_eq!(failed.len(), 1);
+        let record = failed.get(0).unwrap();
+        assert_eq!(record.index, 1);
+        assert_eq!(record.payment.recipient, frozen);
+        assert_eq!(
+            record.outcome,
+            PaymentOutcome::Failed(FailureReason::Deauthorized)
+        );
+    }
+
+    #[test]
+    fn test_retry_failed_after_unfreeze() {
+        let env = Env::default();
+        let (client, admin, payer, _, _) = test_setup::setup_contract(&env);
+        let (batch_id, frozen, token1, token_admin) =
+            setup_frozen_batch(&env, &client, &admin, &payer);
+        client.execute_batch(&batch_id, &None);
+
+        token_admin.set_authorized(&frozen, &true);
+        let progress = client.retry_failed(&batch_id, &None);
+
+        assert_eq!(progress.failed, 0);
+        assert_eq!(check_balance(&env, &token1, &frozen), 2_000);
+        assert_eq!(
+            client.get_payment_outcome(&batch_id, &1),
+            PaymentOutcome::Paid
+        );
+        assert!(client.get_failed_payments(&batch_id).is_empty());
+    }
+
+    #[test]
+    fn test_retry_failed_still_frozen() {
+        let env = Env::default();
+        let (client, admin, payer, _, _) = test_setup::setup_contract(&env);
+        let (batch_id, _, token1, _) = setup_frozen_batch(&env, &client, &admin, &payer);
+        client.execute_batch(&batch_id, &None);
+
+        let progress = client.retry_failed(&batch_id, &None);
+
+        assert_eq!(progress.failed, 1);
+        assert_eq!(client.get_failed_payments(&batch_id).len(), 1);
+        assert_eq!(check_balance(&env, &token1, &payer), 8_500);
+    }
+
+    #[test]
+    fn test_outcomes_of_cancelled_batch() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.execute_next(&batch_id, &1, &None);
+
+        assert_eq!(
+            client.get_payment_outcome(&batch_id, &1),
+            PaymentOutcome::Pending
+        );
+        client.cancel_batch(&payer, &batch_id);
+        assert_eq!(
+            client.get_payment_outcome(&batch_id, &0),
+            PaymentOutcome::Paid
+        );
+        assert_eq!(
+            client.get_payment_outcome(&batch_id, &1),
+            PaymentOutcome::Skipped
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_retry_before_execution() {
+        let env = Env::default();
+        let (client, admin, payer, _, _) = test_setup::setup_contract(&env);
+        let (batch_id, _, _, _) = setup_frozen_batch(&env, &client, &admin, &payer);
+        client.retry_failed(&batch_id, &None);
+    }
+}
+
+mod test_pool_funding {
+    use super::*;
+    use crate::testutils::{MockPool, MockPoolClient};
+    use soroban_sdk::token::StellarAssetClient;
+
+    // A pool holding 5000 of token1 that lets the batch contract pay out `limit`
+    fn setup_pool<'a>(
+        env: &Env,
+        client: &BatchPaymentContractClient,
+        admin: &Address,
+        payer: &Address,
+        token: &Address,
+        limit: i128,
+    ) -> MockPoolClient<'a> {
+        let pool_id = env.register(MockPool, ());
+        let pool = MockPoolClient::new(env, &pool_id);
+        StellarAssetClient::new(env, token).mint(&pool_id, &5_000);
+        pool.set_payout_operator(&client.address, &limit);
+        client.set_pool_payer(admin, &pool_id, payer, &true);
+        pool
+    }
+
+    #[test]
+    fn test_pool_batch_paid_from_pool() {
+        let env = Env::default();
+        let (client, admin, payer, token1, _) = test_setup::setup_contract(&env);
+        let pool = setup_pool(&env, &client, &admin, &payer, &token1, 5_000);
+        let recipient = Address::generate(&env);
+
+        let batch_id = client.create_pool_batch(
+            &payer,
+            &pool.address,
+            &vec![&env, create_payment(&recipient, &token1, 1_500)],
+            &None,
+        );
+        client.execute_batch(&batch_id, &None);
+
+        assert_eq!(client.get_batch(&batch_id).pool, Some(pool.address.clone()));
+        assert_eq!(check_balance(&env, &token1, &recipient), 1_500);
+        assert_eq!(check_balance(&env, &token1, &pool.address), 3_500);
+        assert_eq!(check_balance(&env, &token1, &payer), 10_000);
+    }
+
+    #[test]
+    fn test_pool_limit_fails_row() {
+        let env = Env::default();
+        let (client, admin, payer, token1, _) = test_setup::setup_contract(&env);
+        let pool = setup_pool(&env, &client, &admin, &payer, &token1, 2_000);
+
+        let batch_id = client.create_pool_batch(
+            &payer,
+            &pool.address,
+            &vec![
+                &env,
+                create_payment(&Address::generate(&env), &token1, 1_500),
+                create_payment(&Address::generate(&env), &token1, 1_000),
+            ],
+            &None,
+        );
+        client.execute_batch(&batch_id, &None);
+
+        let failed = client.get_failed_payments(&batch_id);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(
+            failed.get(0).unwrap().outcome,
+            PaymentOutcome::Failed(FailureReason::PoolRejected(14))
+        );
+
+        // Once the pool raises the limit the row can be retried
+        pool.set_payout_operator(&client.address, &1_000);
+        assert_eq!(client.retry_failed(&batch_id, &None).failed, 0);
+        assert_eq!(check_balance(&env, &token1, &pool.address), 2_500);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_pool_batch_unauthorized_payer() {
+        let env = Env::default();
+        let (client, admin, payer, token1, token2) = test_setup::setup_contract(&env);
+        let pool = setup_pool(&env, &client, &admin, &payer, &token1, 5_000);
+        client.set_pool_payer(&admin, &pool.address, &payer, &false);
+        client.create_pool_batch(
+            &payer,
+            &pool.address,
+            &test_setup::payments(&env, &token1, &token2),
+            &None,
+        );
+    }
+}
+
+mod test_idempotency {
+    use super::*;
+    use soroban_sdk::testutils::Ledger;
+    use soroban_sdk::BytesN;
+
+    #[test]
+    fn test_repeated_create_returns_original_batch() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let key = Some(BytesN::from_array(&env, &[1; 32]));
+
+        let first =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &key);
+        let second =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &key);
+
+        assert_eq!(first, second);
+        assert_eq!(
+            client.try_get_batch(&(first + 1)),
+            Err(Ok(BatchError::BatchNotFound))
+        );
+    }
+
+    #[test]
+    fn test_repeated_execute_pays_once() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let key = Some(BytesN::from_array(&env, &[2; 32]));
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+
+        let first = client.execute_next(&batch_id, &1, &key);
+        let second = client.execute_next(&batch_id, &1, &key);
+
+        assert_eq!(first, second);
+        assert_eq!(client.get_progress(&batch_id).processed, 1);
+        assert_eq!(check_balance(&env, &token1, &payer), 9_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Auth, InvalidAction)")]
+    fn test_replayed_key_needs_payer_signature() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let key = Some(BytesN::from_array(&env, &[5; 32]));
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.execute_next(&batch_id, &1, &key);
+
+        // Replaying the key without the payer's signature gets nothing back
+        env.set_auths(&[]);
+        client.execute_next(&batch_id, &1, &key);
+    }
+
+    #[test]
+    fn test_key_runs_again_after_expiry() {
+        let env = Env::default();
+        // Keep the contracts themselves alive past the key's TTL
+        env.ledger().with_mut(|li| {
+            li.min_persistent_entry_ttl = 50_000;
+        });
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let key = Some(BytesN::from_array(&env, &[3; 32]));
+        let batch_id =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &None);
+        client.execute_next(&batch_id, &1, &key);
+
+        env.ledger().with_mut(|li| {
+            li.sequence_number += 17_281;
+        });
+        client.execute_next(&batch_id, &1, &key);
+
+        assert_eq!(client.get_progress(&batch_id).processed, 2);
+    }
+
+    #[test]
+    fn test_keys_are_scoped_to_the_payer() {
+        let env = Env::default();
+        let (client, _, payer, token1, token2) = test_setup::setup_contract(&env);
+        let key = Some(BytesN::from_array(&env, &[4; 32]));
+
+        let first =
+            client.create_batch(&payer, &test_setup::payments(&env, &token1, &token2), &key);
+        let other = client.create_batch(
+            &Address::generate(&env),
+            &test_setup::payments(&env, &token1, &token2),
+            &key,
+        );
+
+        assert_ne!(first, other);
+    }
+}
@@ -0,0 +1,94 @@
+#![cfg(test)]
+extern crate std;
+
+use crate::types::Payment;
+use soroban_sdk::testutils::IssuerFlags;
+use soroban_sdk::{
+    contract, contracterror, contractimpl,
+    token::{StellarAssetClient as TokenAdmin, TokenClient},
+    Address, BytesN, Env,
+};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MockPoolError {
+    Unauthorized = 1,
+    PayoutLimitExceeded = 14,
+}
+
+// Minimal stand-in for a liquidity pool with a single payout operator
+#[contract]
+pub struct MockPool;
+
+#[contractimpl]
+impl MockPool {
+    pub fn set_payout_operator(env: Env, operator: Address, limit: i128) {
+        env.storage().instance().set(&operator, &limit);
+    }
+
+    pub fn operator_payout(
+        env: Env,
+        operator: Address,
+        asset_address: Address,
+        recipient: Address,
+        amount: i128,
+        _idempotency_key: Option<BytesN<32>>,
+    ) -> Result<(), MockPoolError> {
+        operator.require_auth();
+        let limit: i128 = env
+            .storage()
+            .instance()
+            .get(&operator)
+            .ok_or(MockPoolError::Unauthorized)?;
+        if amount > limit {
+            return Err(MockPoolError::PayoutLimitExceeded);
+        }
+        env.storage().instance().set(&operator, &(limit - amount));
+        TokenClient::new(&env, &asset_address).transfer(
+            &env.current_contract_address(),
+            &recipient,
+            &amount,
+        );
+        Ok(())
+    }
+}
+
+// Helper to create a token contract
+pub(crate) fn create_token_contract<'a>(e: &Env, admin: &Address) -> (Address, TokenAdmin<'a>) {
+    let token_contract_id = e.register_stellar_asset_contract_v2(admin.clone());
+    let token = TokenAdmin::new(e, &token_contract_id.address());
+    (token_contract_id.address(), token)
+}
+
+// Helper to create a token whose issuer can freeze balances
+pub(crate) fn create_revocable_token_contract<'a>(
+    e: &Env,
+    admin: &Address,
+) -> (Address, TokenAdmin<'a>) {
+    let token_contract_id = e.register_stellar_asset_contract_v2(admin.clone());
+    token_contract_id
+        .issuer()
+        .set_flag(IssuerFlags::RevocableFlag);
+    let token = TokenAdmin::new(e, &token_contract_id.address());
+    (token_contract_id.address(), token)
+}
+
+// Helper to mint tokens to an address
+pub(crate) fn mint_tokens(token: &TokenAdmin, to: &Address, amount: &i128) {
+    token.mint(to, amount);
+}
+
+// Helper to check token balance
+pub(crate) fn check_balance(e: &Env, token_address: &Address, account: &Address) -> i128 {
+    let token_client = TokenClient::new(e, token_address);
+    token_client.balance(account)
+}
+
+// Helper to build a single payment row
+pub(crate) fn create_payment(recipient: &Address, token: &Address, amount: i128) -> Payment {
+    Payment {
+        recipient: recipient.clone(),
+        token: token.clone(),
+        amount,
+    }
+}
@@ -0,0 +1,140 @@
+use soroban_sdk::{contracterror, contracttype, Address, BytesN, Symbol, Vec};
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BatchStatus {
+    Created,
+    InProgress,
+    Completed,
+    Cancelled,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Payment {
+    pub recipient: Address,
+    pub token: Address,
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Batch {
+    pub id: u64,
+    pub payer: Address,
+    pub payment_count: u32,
+    pub cursor: u32, // Index of the next payment to execute
+    pub status: BatchStatus,
+    pub created_at: u64,
+    pub schedule_id: Option<u64>, // Schedule that produced this batch, if any
+    pub failed_count: u32,        // Processed rows whose transfer failed
+    pub pool: Option<Address>,    // Liquidity pool funding the batch instead of the payer
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchProgress {
+    pub batch_id: u64,
+    pub processed: u32,
+    pub total: u32,
+    pub failed: u32,
+    pub status: BatchStatus,
+}
+
+// Why a single transfer was rejected by the token contract
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FailureReason {
+    Deauthorized,          // Trustline or balance frozen by the issuer
+    MissingTrustline,      // Recipient has no trustline for the asset
+    InsufficientBalance,   // Payer could not cover the row
+    InsufficientAllowance, // Allowance ran out (scheduled batches)
+    TokenError(u32),       // Any other error code returned by the token
+    PoolRejected(u32),     // Error code returned by the funding liquidity pool
+    Aborted,               // The token call trapped without an error code
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PaymentOutcome {
+    Pending,               // Not processed yet
+    Paid,                  // Transfer succeeded
+    Failed(FailureReason), // Transfer failed and can be retried
+    Skipped,               // Never processed because the batch was cancelled
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PaymentRecord {
+    pub index: u32,
+    pub payment: Payment,
+    pub outcome: PaymentOutcome,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ScheduleStatus {
+    Active,
+    Finished,
+    Cancelled,
+}
+
+// What happens when run_due is called after more than one period has passed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CatchUpPolicy {
+    SkipMissed, // Pay once and move the due time past the current time
+    PayMissed,  // Pay one period per call until the schedule has caught up
+}
+
+// A recurring payout. Scheduled batches are pulled from the payer through a
+// token allowance granted to this contract, so that any keeper can run them.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Schedule {
+    pub id: u64,
+    pub payer: Address,
+    pub payments: Vec<Payment>,
+    pub start_time: u64,
+    pub interval: u64,
+    pub end_time: Option<u64>,
+    pub next_due: u64,
+    pub catch_up: CatchUpPolicy,
+    pub runs: u32,
+    pub status: ScheduleStatus,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BatchError {
+    Unauthorized = 1,
+    InvalidAmount = 2,
+    InvalidStatus = 3,
+    EmptyBatch = 4,
+    InsufficientBalance = 5,
+    BatchNotFound = 6,
+    NotInitialized = 7,
+    InvalidLimit = 8,
+    ApprovalRequired = 9,
+    ScheduleNotFound = 10,
+    NotDue = 11,
+    InvalidSchedule = 12,
+    InsufficientAllowance = 13,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    Admin,                                  // Admin address
+    ApprovalManager,                        // Optional approval manager gating batch execution
+    BatchCount,                             // Number of batches created so far
+    Batch(u64),                             // Batch details by ID
+    Payment(u64, u32),                      // Payment row by (batch ID, index)
+    ScheduleCount,                          // Number of schedules created so far
+    Schedule(u64),                          // Schedule details by ID
+    Outcome(u64, u32),                      // Result of a processed payment row
+    FailedRows(u64),                        // Indexes of the rows of a batch that currently failed
+    RowsDigest(u64),                        // Running hash of the payment rows of a batch
+    PoolPayer(Address, Address), // Payer allowed to fund batches from a pool (pool, payer)
+    Processed(Symbol, Address, BytesN<32>), // Stored result of an idempotent call (function, caller, key)
+}
@@ -1,476 +1,2134 @@
 use crate::types::{
-    Asset, Condition, DataKey, DisputeOutcome, DisputeOutcomeOption, DisputeProcess, EscrowError,
-    EscrowStatus,
+    Amendment, AmendmentChange, AmendmentStatus, ArbitrationPanel, Asset, BasketLeg, Condition,
+    ConditionPayout, ConditionTrigger, ConditionType, DataKey, DecisionRule, DisputeOutcome,
+    DisputeOutcomeOption, DisputeProcess, DisputeTerms, Escrow, EscrowError, EscrowStatus,
+    Evidence, FeeSchedule,
 };
-use soroban_sdk::{contract, contractimpl, symbol_short, token::Client, Address, Env, String, Vec};
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{
+    contract, contractimpl, symbol_short, token::Client, Address, Bytes, BytesN, Env, IntoVal, Map,
+    String, Symbol, Vec,
+};
+
+// Seconds the arbitrator has to release an escrow once its conditions are
+// met before the beneficiary can claim the funds directly, unless the admin
+// sets another default
+const DEFAULT_CLAIM_GRACE_PERIOD: u64 = 3 * 24 * 60 * 60;
 
 #[contract]
 pub struct EscrowContract;
 
 #[contractimpl]
 impl EscrowContract {
-    pub fn initialize(
+    pub fn initialize(env: Env, admin: Address) -> Result<(), EscrowError> {
+        if env.storage().persistent().has(&DataKey::Admin) {
+            return Err(EscrowError::Unauthorized);
+        }
+
+        env.storage().persistent().set(&DataKey::Admin, &admin);
+        env.storage().persistent().set(&DataKey::EscrowCount, &0u64);
+
+        Ok(())
+    }
+
+    // Open a new escrow in the registry. It starts out unfunded and is
+    // identified by the returned ID in every later call. Without a deposit
+    // account the contract takes custody of the deposit itself.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_escrow(
         env: Env,
-        admin: Address,
         depositor: Address,
         beneficiary: Address,
         arbitrator: Address,
-        deposit_account: Address,
+        deposit_account: Option<Address>,
         asset: Asset,
         amount: i128,
         timeout_ledger: u32,
-    ) -> Result<(), EscrowError> {
-        if env.storage().persistent().has(&DataKey::Admin) {
-            return Err(EscrowError::Unauthorized);
-        }
+    ) -> Result<u64, EscrowError> {
         if amount <= 0 {
             return Err(EscrowError::InvalidAmount);
         }
 
-        env.storage().persistent().set(&DataKey::Admin, &admin);
-        env.storage()
-            .persistent()
-            .set(&DataKey::Depositor, &depositor);
-        env.storage()
-            .persistent()
-            .set(&DataKey::Beneficiary, &beneficiary);
-        env.storage()
-            .persistent()
-            .set(&DataKey::Arbitrator, &arbitrator);
-        env.storage()
-            .persistent()
-            .set(&DataKey::DepositAccount, &deposit_account);
-        env.storage().persistent().set(&DataKey::Asset, &asset);
-        env.storage().persistent().set(&DataKey::Amount, &amount);
-        env.storage()
-            .persistent()
-            .set(&DataKey::ReleaseConditions, &Vec::<Condition>::new(&env));
-        env.storage().persistent().set(
-            &DataKey::TimeoutTime,
-            &(env.ledger().timestamp() + (timeout_ledger as u64 * 5)),
-        );
+        depositor.require_auth();
 
-        // Initialize DisputeProcess with DisputeOutcomeOption::None
-        let dispute_process = DisputeProcess {
-            initiator: admin.clone(),
-            reason: String::from_str(&env, ""),
-            is_active: false,
-            outcome: DisputeOutcomeOption::None,
+        let escrow_id = Self::get_escrow_count(&env)? + 1;
+        let escrow = Escrow {
+            id: escrow_id,
+            depositor: depositor.clone(),
+            beneficiary: beneficiary.clone(),
+            arbitrator: arbitrator.clone(),
+            deposit_account,
+            asset,
+            amount,
+            funded: 0,
+            held: 0,
+            released: 0,
+            paid_out: 0,
+            legs: Vec::new(&env),
+            conditions_met_at: 0,
+            // The escrow keeps the grace period in force when it was created
+            claim_grace_period: env
+                .storage()
+                .persistent()
+                .get(&DataKey::ClaimGracePeriod)
+                .unwrap_or(DEFAULT_CLAIM_GRACE_PERIOD),
+            timeout_time: env.ledger().timestamp() + (timeout_ledger as u64 * 5),
+            status: EscrowStatus::Initialized,
+            created_at: env.ledger().timestamp(),
         };
+        Self::save_escrow(&env, &escrow);
+        // The escrow keeps the fee schedule in force when it was created
+        let fees: Option<FeeSchedule> = env.storage().persistent().get(&DataKey::FeeSchedule);
+        if let Some(fees) = fees {
+            env.storage()
+                .persistent()
+                .set(&DataKey::Fees(escrow_id), &fees);
+        }
+        env.storage().persistent().set(
+            &DataKey::ReleaseConditions(escrow_id),
+            &Vec::<Condition>::new(&env),
+        );
         env.storage()
             .persistent()
-            .set(&DataKey::DisputeResolution, &Some(dispute_process));
-        env.storage()
-            .persistent()
-            .set(&DataKey::Status, &EscrowStatus::Initialized);
+            .set(&DataKey::EscrowCount, &escrow_id);
+
+        // Index the escrow under each of its parties
+        Self::add_to_index(
+            &env,
+            DataKey::DepositorEscrows(depositor.clone()),
+            escrow_id,
+        );
+        Self::add_to_index(
+            &env,
+            DataKey::BeneficiaryEscrows(beneficiary.clone()),
+            escrow_id,
+        );
+        Self::add_to_index(
+            &env,
+            DataKey::ArbitratorEscrows(arbitrator.clone()),
+            escrow_id,
+        );
 
         env.events().publish(
-            (symbol_short!("created"), depositor, beneficiary, arbitrator),
+            (symbol_short!("created"), escrow_id, depositor),
+            (beneficiary, arbitrator, amount),
+        );
+
+        Ok(escrow_id)
+    }
+
+    pub fn deposit_funds(
+        env: Env,
+        escrow_id: u64,
+        depositor: Address,
+        amount: i128,
+    ) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+
+        if depositor != escrow.depositor {
+            return Err(EscrowError::Unauthorized);
+        }
+        if escrow.status != EscrowStatus::Initialized {
+            return Err(EscrowError::InvalidStatus);
+        }
+        // Deposits may come in installments but never overshoot the target
+        if amount <= 0 || escrow.funded + amount > escrow.amount {
+            return Err(EscrowError::InvalidAmount);
+        }
+
+        depositor.require_auth();
+
+        Self::transfer_in(
+            &env,
+            &escrow.deposit_account,
+            &escrow.asset.token,
+            &depositor,
             amount,
         );
+        if escrow.deposit_account.is_none() {
+            escrow.held += amount;
+        }
+        escrow.funded += amount;
+
+        // Basket legs are deposited in step with the escrow amount
+        let mut legs = escrow.legs.clone();
+        for index in 0..legs.len() {
+            let mut leg = legs.get_unchecked(index);
+            let due = leg.amount * escrow.funded / escrow.amount;
+            Self::transfer_in(
+                &env,
+                &escrow.deposit_account,
+                &leg.asset.token,
+                &depositor,
+                due - leg.funded,
+            );
+            leg.funded = due;
+            legs.set(index, leg);
+        }
+        escrow.legs = legs;
+
+        if escrow.funded == escrow.amount {
+            escrow.status = EscrowStatus::Funded;
+        }
+        Self::save_escrow(&env, &escrow);
+
+        env.events().publish(
+            (symbol_short!("deposited"), escrow_id, depositor),
+            (amount, escrow.funded),
+        );
 
         Ok(())
     }
 
-    pub fn deposit_funds(env: Env, depositor: Address, amount: i128) -> Result<(), EscrowError> {
-        let stored_depositor = Self::get_depositor(&env)?;
-        let status = Self::get_status(&env)?;
-        let stored_amount = Self::get_amount(&env)?;
-        let asset = Self::get_asset(&env)?;
-        let deposit_account = Self::get_deposit_account(&env)?;
+    // Lock another asset in the escrow. Legs are fixed before the first deposit.
+    pub fn add_basket_leg(
+        env: Env,
+        escrow_id: u64,
+        depositor: Address,
+        asset: Asset,
+        amount: i128,
+    ) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
 
-        if depositor != stored_depositor {
+        if depositor != escrow.depositor {
             return Err(EscrowError::Unauthorized);
         }
-        if status != EscrowStatus::Initialized {
+        if escrow.status != EscrowStatus::Initialized || escrow.funded > 0 {
             return Err(EscrowError::InvalidStatus);
         }
-        if amount != stored_amount {
+        if amount <= 0 {
             return Err(EscrowError::InvalidAmount);
         }
+        if asset.token == escrow.asset.token
+            || escrow.legs.iter().any(|leg| leg.asset.token == asset.token)
+        {
+            return Err(EscrowError::DuplicateAsset);
+        }
 
         depositor.require_auth();
 
-        let token_client = Client::new(&env, &asset.token);
-        token_client.transfer(&depositor, &deposit_account, &amount);
-
-        env.storage()
-            .persistent()
-            .set(&DataKey::Status, &EscrowStatus::Funded);
+        escrow.legs.push_back(BasketLeg {
+            asset: asset.clone(),
+            amount,
+            funded: 0,
+            paid_out: 0,
+        });
+        Self::save_escrow(&env, &escrow);
 
         env.events()
-            .publish((symbol_short!("deposited"), depositor), amount);
+            .publish((symbol_short!("leg_add"), escrow_id, asset.token), amount);
 
         Ok(())
     }
 
     pub fn add_release_condition(
         env: Env,
+        escrow_id: u64,
         caller: Address,
         condition: Condition,
     ) -> Result<(), EscrowError> {
-        let arbitrator = Self::get_arbitrator(&env)?;
-        let status = Self::get_status(&env)?;
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
 
-        if caller != arbitrator {
+        if caller != escrow.arbitrator {
             return Err(EscrowError::Unauthorized);
         }
-        if status != EscrowStatus::Initialized && status != EscrowStatus::Funded {
+        if escrow.status != EscrowStatus::Initialized && escrow.status != EscrowStatus::Funded {
             return Err(EscrowError::InvalidStatus);
         }
-        if condition.is_fulfilled {
+        if condition.is_fulfilled || !Self::valid_trigger(&condition) {
             return Err(EscrowError::InvalidCondition);
         }
 
+        // Milestones together may not pay out more than the escrow holds
+        let mut conditions = Self::get_conditions(&env, escrow_id);
+        let milestone_amount = Self::milestone_amount(&escrow, &condition.payout)?;
+        let committed: i128 = conditions
+            .iter()
+            .map(|c| Self::milestone_amount(&escrow, &c.payout).unwrap_or(0))
+            .sum();
+        if committed + milestone_amount > escrow.amount {
+            return Err(EscrowError::InvalidAmount);
+        }
+
         caller.require_auth();
 
-        let mut conditions: Vec<Condition> = env
-            .storage()
-            .persistent()
-            .get(&DataKey::ReleaseConditions)
-            .unwrap_or_else(|| Vec::new(&env));
         conditions.push_back(condition.clone());
         env.storage()
             .persistent()
-            .set(&DataKey::ReleaseConditions, &conditions);
+            .set(&DataKey::ReleaseConditions(escrow_id), &conditions);
 
-        env.events()
-            .publish((symbol_short!("cond_add"),), condition.description);
+        env.events().publish(
+            (symbol_short!("cond_add"), escrow_id),
+            condition.description,
+        );
 
         Ok(())
     }
 
     pub fn verify_condition(
         env: Env,
+        escrow_id: u64,
         arbitrator: Address,
         condition_index: u32,
     ) -> Result<(), EscrowError> {
-        let stored_arbitrator = Self::get_arbitrator(&env)?;
-        let status = Self::get_status(&env)?;
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
 
-        if arbitrator != stored_arbitrator {
+        if arbitrator != escrow.arbitrator {
             return Err(EscrowError::Unauthorized);
         }
-        if status != EscrowStatus::Funded && status != EscrowStatus::ConditionsMet {
+        if escrow.status != EscrowStatus::Funded && escrow.status != EscrowStatus::ConditionsMet {
             return Err(EscrowError::InvalidStatus);
         }
 
         arbitrator.require_auth();
 
-        let mut conditions: Vec<Condition> = env
-            .storage()
-            .persistent()
-            .get(&DataKey::ReleaseConditions)
+        let mut conditions = Self::get_conditions(&env, escrow_id);
+
+        let condition = conditions
+            .get(condition_index)
             .ok_or(EscrowError::InvalidCondition)?;
 
+        if condition.is_fulfilled {
+            return Err(EscrowError::AlreadyFulfilled);
+        }
+        // Only manual conditions and unlocked time-based conditions can be
+        // verified; the others are fulfilled by their own signers or proofs
+        if !Self::is_unlocked(&env, &condition.trigger) {
+            return Err(EscrowError::ConditionLocked);
+        }
+
+        Self::fulfill_condition(&env, &mut escrow, &mut conditions, condition_index)?;
+        Self::save_conditions(&env, &mut escrow, &conditions);
+
+        env.events()
+            .publish((symbol_short!("cond_ver"), escrow_id), condition_index);
+
+        Ok(())
+    }
+
+    // Approve a MultiSig condition as one of its signers. The condition is
+    // fulfilled once the threshold of approvals is reached.
+    pub fn approve_condition(
+        env: Env,
+        escrow_id: u64,
+        signer: Address,
+        condition_index: u32,
+    ) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+
+        if escrow.status != EscrowStatus::Funded && escrow.status != EscrowStatus::ConditionsMet {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        let mut conditions = Self::get_conditions(&env, escrow_id);
         let condition = conditions
             .get(condition_index)
             .ok_or(EscrowError::InvalidCondition)?;
+        let ConditionTrigger::MultiSig(signers, threshold) = condition.trigger else {
+            return Err(EscrowError::InvalidCondition);
+        };
 
         if condition.is_fulfilled {
             return Err(EscrowError::AlreadyFulfilled);
         }
+        if !signers.contains(&signer) {
+            return Err(EscrowError::Unauthorized);
+        }
 
-        let mut condition = condition.clone();
-        condition.is_fulfilled = true;
-        conditions.set(condition_index, condition);
-        env.storage()
-            .persistent()
-            .set(&DataKey::ReleaseConditions, &conditions);
+        signer.require_auth();
 
-        let all_conditions_met = conditions.iter().all(|c| c.is_fulfilled);
-        if all_conditions_met {
-            env.storage()
-                .persistent()
-                .set(&DataKey::Status, &EscrowStatus::ConditionsMet);
+        let mut approvals = Self::get_approvals(&env, escrow_id, condition_index);
+        if approvals.contains(&signer) {
+            return Err(EscrowError::AlreadyApproved);
+        }
+        approvals.push_back(signer.clone());
+        env.storage().persistent().set(
+            &DataKey::ConditionApprovals(escrow_id, condition_index),
+            &approvals,
+        );
+
+        if approvals.len() >= threshold {
+            Self::fulfill_condition(&env, &mut escrow, &mut conditions, condition_index)?;
+            Self::save_conditions(&env, &mut escrow, &conditions);
+        }
+
+        env.events().publish(
+            (symbol_short!("cond_appr"), escrow_id, signer),
+            (condition_index, approvals.len()),
+        );
+
+        Ok(())
+    }
+
+    // Withdraw a signer's approval of a MultiSig condition that has not been
+    // fulfilled yet
+    pub fn withdraw_approval(
+        env: Env,
+        escrow_id: u64,
+        signer: Address,
+        condition_index: u32,
+    ) -> Result<(), EscrowError> {
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+
+        if escrow.status != EscrowStatus::Funded && escrow.status != EscrowStatus::ConditionsMet {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        let condition = Self::get_conditions(&env, escrow_id)
+            .get(condition_index)
+            .ok_or(EscrowError::InvalidCondition)?;
+
+        if condition.is_fulfilled {
+            return Err(EscrowError::AlreadyFulfilled);
+        }
+
+        let mut approvals = Self::get_approvals(&env, escrow_id, condition_index);
+        let position = approvals
+            .first_index_of(&signer)
+            .ok_or(EscrowError::NotApproved)?;
+
+        signer.require_auth();
+
+        approvals.remove(position);
+        env.storage().persistent().set(
+            &DataKey::ConditionApprovals(escrow_id, condition_index),
+            &approvals,
+        );
+
+        env.events().publish(
+            (symbol_short!("cond_wdr"), escrow_id, signer),
+            (condition_index, approvals.len()),
+        );
+
+        Ok(())
+    }
+
+    // Fulfil every time-based condition whose unlock point has passed and
+    // every oracle condition its oracle attests. Anyone may call this;
+    // returns how many conditions were fulfilled.
+    pub fn evaluate_conditions(env: Env, escrow_id: u64) -> Result<u32, EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+
+        if escrow.status != EscrowStatus::Funded && escrow.status != EscrowStatus::ConditionsMet {
+            return Err(EscrowError::InvalidStatus);
         }
 
+        let mut conditions = Self::get_conditions(&env, escrow_id);
+        let mut fulfilled = 0;
+        for index in 0..conditions.len() {
+            let condition = conditions.get_unchecked(index);
+            if condition.is_fulfilled || !Self::is_triggered(&env, &condition.trigger) {
+                continue;
+            }
+
+            Self::fulfill_condition(&env, &mut escrow, &mut conditions, index)?;
+            fulfilled += 1;
+
+            env.events()
+                .publish((symbol_short!("cond_auto"), escrow_id), index);
+        }
+
+        if fulfilled > 0 {
+            Self::save_conditions(&env, &mut escrow, &conditions);
+        }
+
+        Ok(fulfilled)
+    }
+
+    // Fulfil a signed-attestation condition with the attester's signature
+    // over the condition's message. Anyone can submit it; an invalid
+    // signature fails the call.
+    pub fn submit_attestation(
+        env: Env,
+        escrow_id: u64,
+        condition_index: u32,
+        signature: BytesN<64>,
+    ) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+
+        if escrow.status != EscrowStatus::Funded && escrow.status != EscrowStatus::ConditionsMet {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        let mut conditions = Self::get_conditions(&env, escrow_id);
+        let condition = conditions
+            .get(condition_index)
+            .ok_or(EscrowError::InvalidCondition)?;
+        let ConditionTrigger::Attestation(public_key, template) = condition.trigger else {
+            return Err(EscrowError::InvalidCondition);
+        };
+        if condition.is_fulfilled {
+            return Err(EscrowError::AlreadyFulfilled);
+        }
+
+        let message = Self::attestation_message(template, escrow_id, condition_index);
+        env.crypto()
+            .ed25519_verify(&public_key, &message, &signature);
+
+        Self::fulfill_condition(&env, &mut escrow, &mut conditions, condition_index)?;
+        Self::save_conditions(&env, &mut escrow, &conditions);
+
         env.events()
-            .publish((symbol_short!("cond_ver"),), condition_index);
+            .publish((symbol_short!("attested"), escrow_id), condition_index);
 
         Ok(())
     }
 
-    pub fn release_funds(env: Env, caller: Address) -> Result<(), EscrowError> {
-        let arbitrator = Self::get_arbitrator(&env)?;
-        let status = Self::get_status(&env)?;
-        let dispute = Self::get_dispute_resolution(&env)?;
-        let asset = Self::get_asset(&env)?;
-        let deposit_account = Self::get_deposit_account(&env)?;
-        let beneficiary = Self::get_beneficiary(&env)?;
-        let amount = Self::get_amount(&env)?;
+    pub fn release_funds(env: Env, escrow_id: u64, caller: Address) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        let dispute = Self::get_dispute_resolution(&env, escrow_id);
 
-        if caller != arbitrator {
+        // The beneficiary can claim without the arbitrator once the
+        // conditions have been met for the grace period
+        let claiming = caller == escrow.beneficiary;
+        if caller != escrow.arbitrator && !claiming {
             return Err(EscrowError::Unauthorized);
         }
-        if status != EscrowStatus::ConditionsMet {
+        if escrow.status != EscrowStatus::ConditionsMet {
             return Err(EscrowError::ConditionsNotMet);
         }
         if dispute.is_some() && dispute.as_ref().unwrap().is_active {
             return Err(EscrowError::DisputeInProgress);
         }
+        if claiming
+            && env.ledger().timestamp() < escrow.conditions_met_at + escrow.claim_grace_period
+        {
+            return Err(EscrowError::GracePeriodActive);
+        }
 
         caller.require_auth();
 
-        // Would work in multi-signatory scenario and remove need for transfer_from
-        // deposit_account.require_auth();
-        // token_client.transfer(&deposit_account, &beneficiary, &amount);
-
-        // Create token client and release funds using transfer_from with contract as spender
-        let token_client = Client::new(&env, &asset.token);
-        token_client.transfer_from(
-            &env.current_contract_address(),
-            &deposit_account,
-            &beneficiary,
-            &amount,
-        );
+        Self::release(&env, &mut escrow)
+    }
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Status, &EscrowStatus::Released);
+    // Claim a hash-time-locked condition by revealing the preimage of its
+    // hashlock before the timelock expires. The preimage is published so the
+    // counterparty leg of a cross-chain swap can be claimed with it; once
+    // every condition is met the escrow is released to the beneficiary.
+    pub fn claim_with_preimage(
+        env: Env,
+        escrow_id: u64,
+        beneficiary: Address,
+        condition_index: u32,
+        preimage: Bytes,
+    ) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
 
-        env.events()
-            .publish((symbol_short!("released"), beneficiary), amount);
+        if beneficiary != escrow.beneficiary {
+            return Err(EscrowError::Unauthorized);
+        }
+        if escrow.status != EscrowStatus::Funded {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        let mut conditions = Self::get_conditions(&env, escrow_id);
+        let condition = conditions
+            .get(condition_index)
+            .ok_or(EscrowError::InvalidCondition)?;
+        let ConditionTrigger::HashLock(hashlock, timelock) = condition.trigger else {
+            return Err(EscrowError::InvalidCondition);
+        };
+        if condition.is_fulfilled {
+            return Err(EscrowError::AlreadyFulfilled);
+        }
+        if env.ledger().timestamp() >= timelock {
+            return Err(EscrowError::HashlockExpired);
+        }
+        if env.crypto().sha256(&preimage).to_bytes() != hashlock {
+            return Err(EscrowError::InvalidPreimage);
+        }
+
+        beneficiary.require_auth();
+
+        Self::fulfill_condition(&env, &mut escrow, &mut conditions, condition_index)?;
+        Self::save_conditions(&env, &mut escrow, &conditions);
+
+        env.events().publish(
+            (symbol_short!("preimage"), escrow_id, condition_index),
+            preimage,
+        );
+
+        if escrow.status == EscrowStatus::ConditionsMet {
+            Self::release(&env, &mut escrow)?;
+        }
 
         Ok(())
     }
 
-    pub fn refund_deposit(env: Env, caller: Address) -> Result<(), EscrowError> {
-        let arbitrator = Self::get_arbitrator(&env)?;
-        let status = Self::get_status(&env)?;
-        let timeout_time = Self::get_timeout_time(&env)?;
-        let asset = Self::get_asset(&env)?;
-        let deposit_account = Self::get_deposit_account(&env)?;
-        let depositor = Self::get_depositor(&env)?;
-        let amount = Self::get_amount(&env)?;
+    pub fn refund_deposit(env: Env, escrow_id: u64, caller: Address) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
 
-        if caller != arbitrator {
+        // The depositor can pull the refund without the arbitrator
+        if caller != escrow.arbitrator && caller != escrow.depositor {
             return Err(EscrowError::Unauthorized);
         }
-        if status != EscrowStatus::Funded {
+        // A partly funded escrow can be refunded too
+        let partly_funded = escrow.status == EscrowStatus::Initialized && escrow.funded > 0;
+        if escrow.status != EscrowStatus::Funded && !partly_funded {
             return Err(EscrowError::ContractNotFunded);
         }
-        if env.ledger().timestamp() < timeout_time {
+        // An expired hashlock can no longer be claimed, so the escrow can be
+        // refunded from then on
+        if env.ledger().timestamp() < escrow.timeout_time && !Self::hashlock_expired(&env, &escrow)
+        {
             return Err(EscrowError::TimeoutNotReached);
         }
 
         caller.require_auth();
 
-        // Create token client and release funds
-        let token_client = Client::new(&env, &asset.token);
-        token_client.transfer_from(
-            &env.current_contract_address(),
-            &deposit_account,
-            &depositor,
-            &amount,
-        );
+        // Only what was actually deposited goes back; milestones already paid
+        // out stay with the beneficiary
+        let (depositor, amount) = (escrow.depositor.clone(), escrow.funded - escrow.released);
+        Self::pay_out(&env, &mut escrow, &depositor, amount);
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Status, &EscrowStatus::Refunded);
+        escrow.status = EscrowStatus::Refunded;
+        Self::save_escrow(&env, &escrow);
 
         env.events()
-            .publish((symbol_short!("refunded"), depositor), amount);
+            .publish((symbol_short!("refunded"), escrow_id, depositor), amount);
 
         Ok(())
     }
 
-    pub fn initiate_dispute(
+    // Propose a change to a live escrow. The depositor or the beneficiary
+    // proposes and the other party has to approve it; only one amendment can
+    // be open at a time. Returns the version the proposal is recorded under.
+    pub fn propose_amendment(
         env: Env,
-        initiator: Address,
-        reason: String,
-    ) -> Result<(), EscrowError> {
-        let depositor = Self::get_depositor(&env)?;
-        let beneficiary = Self::get_beneficiary(&env)?;
-        let status = Self::get_status(&env)?;
-        let dispute = Self::get_dispute_resolution(&env)?;
+        escrow_id: u64,
+        proposer: Address,
+        change: AmendmentChange,
+    ) -> Result<u32, EscrowError> {
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
 
-        if initiator != depositor && initiator != beneficiary {
+        if proposer != escrow.depositor && proposer != escrow.beneficiary {
             return Err(EscrowError::Unauthorized);
         }
-        // Allow disputes for both Funded and ConditionsMet status
-        if status != EscrowStatus::Funded && status != EscrowStatus::ConditionsMet {
-            return Err(EscrowError::InvalidStatus);
-        }
-        if dispute.is_some() && dispute.as_ref().unwrap().is_active {
-            return Err(EscrowError::DisputeInProgress);
+        Self::check_amendment(&env, &escrow, &change)?;
+        let mut amendments = Self::get_amendment_list(&env, escrow_id);
+        if amendments
+            .iter()
+            .any(|amendment| amendment.status == AmendmentStatus::Proposed)
+        {
+            return Err(EscrowError::AmendmentPending);
         }
 
-        initiator.require_auth();
-
-        let dispute = DisputeProcess {
-            initiator: initiator.clone(),
-            reason,
-            is_active: true,
-            outcome: DisputeOutcomeOption::None,
-        };
-
-        env.storage()
-            .persistent()
-            .set(&DataKey::DisputeResolution, &Some(dispute));
+        proposer.require_auth();
+
+        let version = amendments.len() + 1;
+        amendments.push_back(Amendment {
+            version,
+            proposer: proposer.clone(),
+            change,
+            status: AmendmentStatus::Proposed,
+            proposed_at: env.ledger().timestamp(),
+            decided_at: 0,
+        });
         env.storage()
             .persistent()
-            .set(&DataKey::Status, &EscrowStatus::InDispute);
+            .set(&DataKey::Amendments(escrow_id), &amendments);
 
         env.events()
-            .publish((symbol_short!("disp_init"), initiator), true);
+            .publish((symbol_short!("amend_req"), escrow_id, proposer), version);
 
-        Ok(())
+        Ok(version)
     }
 
-    pub fn resolve_dispute(
+    // Approve the open amendment and apply it. Only the counterparty of the
+    // proposer can approve. Raising the amount of a funded escrow pulls the
+    // top-up from the depositor, lowering it refunds the difference.
+    pub fn approve_amendment(
         env: Env,
-        arbitrator: Address,
-        outcome: DisputeOutcome,
+        escrow_id: u64,
+        approver: Address,
+        version: u32,
     ) -> Result<(), EscrowError> {
-        let stored_arbitrator = Self::get_arbitrator(&env)?;
-        let dispute = Self::get_dispute_resolution(&env)?;
-        let asset = Self::get_asset(&env)?;
-        let deposit_account = Self::get_deposit_account(&env)?;
-        let beneficiary = Self::get_beneficiary(&env)?;
-        let depositor = Self::get_depositor(&env)?;
-        let amount = Self::get_amount(&env)?;
-
-        if arbitrator != stored_arbitrator {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        let (mut amendments, mut amendment) = Self::get_open_amendment(&env, escrow_id, version)?;
+
+        if approver == amendment.proposer
+            || (approver != escrow.depositor && approver != escrow.beneficiary)
+        {
             return Err(EscrowError::Unauthorized);
         }
-        let dispute = dispute.ok_or(EscrowError::NoDispute)?;
-        if !dispute.is_active {
-            return Err(EscrowError::NoDispute);
-        }
+        // The escrow may have moved on since the proposal
+        Self::check_amendment(&env, &escrow, &amendment.change)?;
 
-        arbitrator.require_auth();
+        approver.require_auth();
 
-        let token_client = Client::new(&env, &asset.token);
+        Self::apply_amendment(&env, &mut escrow, &approver, &amendment.change);
+        Self::save_escrow(&env, &escrow);
 
-        match outcome {
-            DisputeOutcome::ReleaseToBeneficiary => {
-                token_client.transfer_from(
-                    &env.current_contract_address(),
-                    &deposit_account,
-                    &beneficiary,
-                    &amount,
-                );
+        amendment.status = AmendmentStatus::Approved;
+        amendment.decided_at = env.ledger().timestamp();
+        amendments.set(version - 1, amendment.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::Amendments(escrow_id), &amendments);
+
+        env.events().publish(
+            (symbol_short!("amended"), escrow_id, version),
+            amendment.change,
+        );
+
+        Ok(())
+    }
+
+    // Close the open amendment without applying it: the counterparty rejects
+    // it or the proposer withdraws it
+    pub fn reject_amendment(
+        env: Env,
+        escrow_id: u64,
+        caller: Address,
+        version: u32,
+    ) -> Result<(), EscrowError> {
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        let (mut amendments, mut amendment) = Self::get_open_amendment(&env, escrow_id, version)?;
+
+        if caller != escrow.depositor && caller != escrow.beneficiary {
+            return Err(EscrowError::Unauthorized);
+        }
+
+        caller.require_auth();
+
+        amendment.status = AmendmentStatus::Rejected;
+        amendment.decided_at = env.ledger().timestamp();
+        amendments.set(version - 1, amendment);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Amendments(escrow_id), &amendments);
+
+        env.events()
+            .publish((symbol_short!("amend_rej"), escrow_id, caller), version);
+
+        Ok(())
+    }
+
+    pub fn initiate_dispute(
+        env: Env,
+        escrow_id: u64,
+        initiator: Address,
+        reason: String,
+    ) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        let dispute = Self::get_dispute_resolution(&env, escrow_id);
+
+        if initiator != escrow.depositor && initiator != escrow.beneficiary {
+            return Err(EscrowError::Unauthorized);
+        }
+        // Allow disputes for both Funded and ConditionsMet status
+        if escrow.status != EscrowStatus::Funded && escrow.status != EscrowStatus::ConditionsMet {
+            return Err(EscrowError::InvalidStatus);
+        }
+        if dispute.is_some() && dispute.as_ref().unwrap().is_active {
+            return Err(EscrowError::DisputeInProgress);
+        }
+
+        initiator.require_auth();
+
+        // The parties get the evidence period from now on, and a panel has
+        // its voting window after that
+        let evidence_ends = match Self::get_terms(&env, escrow_id) {
+            Some(terms) => env.ledger().timestamp() + terms.evidence_period,
+            None => env.ledger().timestamp(),
+        };
+        let voting_ends = match Self::get_panel(&env, escrow_id) {
+            Some(panel) => evidence_ends + panel.voting_period,
+            None => 0,
+        };
+        let dispute = DisputeProcess {
+            initiator: initiator.clone(),
+            reason,
+            is_active: true,
+            outcome: DisputeOutcomeOption::None,
+            voting_ends,
+            evidence_ends,
+            ruling: DisputeOutcomeOption::None,
+            appeal_ends: 0,
+            appealed: false,
+        };
+        env.storage()
+            .persistent()
+            .remove(&DataKey::DisputeVotes(escrow_id));
+        env.storage()
+            .persistent()
+            .remove(&DataKey::DisputeEvidence(escrow_id));
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::DisputeResolution(escrow_id), &dispute);
+        escrow.status = EscrowStatus::InDispute;
+        Self::save_escrow(&env, &escrow);
+
+        env.events()
+            .publish((symbol_short!("disp_init"), escrow_id, initiator), true);
+
+        Ok(())
+    }
+
+    pub fn resolve_dispute(
+        env: Env,
+        escrow_id: u64,
+        arbitrator: Address,
+        outcome: DisputeOutcome,
+    ) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        let dispute = Self::get_dispute_resolution(&env, escrow_id);
+
+        if arbitrator != escrow.arbitrator {
+            return Err(EscrowError::Unauthorized);
+        }
+        let dispute = dispute.ok_or(EscrowError::NoDispute)?;
+        if !dispute.is_active {
+            return Err(EscrowError::NoDispute);
+        }
+        // Escrows with a panel, and appealed disputes, are decided by vote
+        if Self::deciding_panel(&env, escrow_id, &dispute).is_some() {
+            return Err(EscrowError::Unauthorized);
+        }
+        Self::check_ready_to_rule(&env, &dispute)?;
+
+        arbitrator.require_auth();
+
+        Self::rule(&env, &mut escrow, dispute, outcome)?;
+
+        env.events()
+            .publish((symbol_short!("disp_res"), escrow_id, arbitrator), true);
+
+        Ok(())
+    }
+
+    // Attach a document to the escrow's dispute while the evidence period is open
+    pub fn submit_evidence(
+        env: Env,
+        escrow_id: u64,
+        submitter: Address,
+        digest: BytesN<32>,
+        uri: String,
+    ) -> Result<(), EscrowError> {
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        let dispute =
+            Self::get_dispute_resolution(&env, escrow_id).ok_or(EscrowError::NoDispute)?;
+
+        if submitter != escrow.depositor && submitter != escrow.beneficiary {
+            return Err(EscrowError::Unauthorized);
+        }
+        if !dispute.is_active {
+            return Err(EscrowError::NoDispute);
+        }
+        if env.ledger().timestamp() >= dispute.evidence_ends {
+            return Err(EscrowError::EvidenceClosed);
+        }
+
+        submitter.require_auth();
+
+        let mut evidence = Self::get_evidence_list(&env, escrow_id);
+        evidence.push_back(Evidence {
+            submitter: submitter.clone(),
+            digest: digest.clone(),
+            uri,
+            submitted_at: env.ledger().timestamp(),
+        });
+        env.storage()
+            .persistent()
+            .set(&DataKey::DisputeEvidence(escrow_id), &evidence);
+
+        env.events()
+            .publish((symbol_short!("evidence"), escrow_id, submitter), digest);
+
+        Ok(())
+    }
+
+    // Appeal a pending ruling, handing the dispute to the appeal panel
+    pub fn appeal_ruling(env: Env, escrow_id: u64, appellant: Address) -> Result<(), EscrowError> {
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        let mut dispute =
+            Self::get_dispute_resolution(&env, escrow_id).ok_or(EscrowError::NoDispute)?;
+
+        if appellant != escrow.depositor && appellant != escrow.beneficiary {
+            return Err(EscrowError::Unauthorized);
+        }
+        if !dispute.is_active {
+            return Err(EscrowError::NoDispute);
+        }
+        if dispute.ruling == DisputeOutcomeOption::None
+            || env.ledger().timestamp() >= dispute.appeal_ends
+        {
+            return Err(EscrowError::AppealClosed);
+        }
+        let terms = Self::get_terms(&env, escrow_id).ok_or(EscrowError::AppealClosed)?;
+
+        appellant.require_auth();
+
+        dispute.ruling = DisputeOutcomeOption::None;
+        dispute.appeal_ends = 0;
+        dispute.appealed = true;
+        dispute.voting_ends = env.ledger().timestamp() + terms.appeal_panel.voting_period;
+        env.storage()
+            .persistent()
+            .remove(&DataKey::DisputeVotes(escrow_id));
+        env.storage()
+            .persistent()
+            .set(&DataKey::DisputeResolution(escrow_id), &dispute);
+
+        env.events()
+            .publish((symbol_short!("appealed"), escrow_id, appellant), true);
+
+        Ok(())
+    }
+
+    // Pay out a ruling nobody appealed. Anyone may call this once the appeal
+    // window has closed.
+    pub fn finalize_dispute(env: Env, escrow_id: u64) -> Result<DisputeOutcome, EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        let dispute =
+            Self::get_dispute_resolution(&env, escrow_id).ok_or(EscrowError::NoDispute)?;
+
+        if !dispute.is_active {
+            return Err(EscrowError::NoDispute);
+        }
+        let DisputeOutcomeOption::Some(outcome) = dispute.ruling.clone() else {
+            return Err(EscrowError::InvalidStatus);
+        };
+        if env.ledger().timestamp() < dispute.appeal_ends {
+            return Err(EscrowError::AppealOpen);
+        }
+
+        Self::execute_outcome(&env, &mut escrow, dispute, outcome.clone())?;
+
+        env.events()
+            .publish((symbol_short!("disp_fin"), escrow_id), outcome.clone());
+
+        Ok(outcome)
+    }
+
+    // Set the evidence period, appeal window and appeal panel of the escrow's disputes
+    pub fn set_dispute_terms(
+        env: Env,
+        escrow_id: u64,
+        arbitrator: Address,
+        terms: DisputeTerms,
+    ) -> Result<(), EscrowError> {
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+
+        if arbitrator != escrow.arbitrator {
+            return Err(EscrowError::Unauthorized);
+        }
+        if escrow.status != EscrowStatus::Initialized
+            && escrow.status != EscrowStatus::Funded
+            && escrow.status != EscrowStatus::ConditionsMet
+        {
+            return Err(EscrowError::InvalidStatus);
+        }
+        if terms.appeal_window == 0 || !Self::valid_panel(&terms.appeal_panel) {
+            return Err(EscrowError::InvalidPanel);
+        }
+
+        arbitrator.require_auth();
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::DisputeTerms(escrow_id), &terms);
+
+        env.events().publish(
+            (symbol_short!("terms"), escrow_id, arbitrator),
+            (terms.evidence_period, terms.appeal_window),
+        );
+
+        Ok(())
+    }
+
+    // Hand the escrow's disputes to a panel of arbitrators deciding by vote
+    pub fn set_arbitration_panel(
+        env: Env,
+        escrow_id: u64,
+        arbitrator: Address,
+        panel: ArbitrationPanel,
+    ) -> Result<(), EscrowError> {
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+
+        if arbitrator != escrow.arbitrator {
+            return Err(EscrowError::Unauthorized);
+        }
+        if escrow.status != EscrowStatus::Initialized
+            && escrow.status != EscrowStatus::Funded
+            && escrow.status != EscrowStatus::ConditionsMet
+        {
+            return Err(EscrowError::InvalidStatus);
+        }
+        if !Self::valid_panel(&panel) {
+            return Err(EscrowError::InvalidPanel);
+        }
+
+        arbitrator.require_auth();
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::ArbitrationPanel(escrow_id), &panel);
+
+        env.events().publish(
+            (symbol_short!("panel"), escrow_id, arbitrator),
+            panel.members.len(),
+        );
+
+        Ok(())
+    }
+
+    // Cast or change a panel member's vote while the voting window is open
+    pub fn vote_on_dispute(
+        env: Env,
+        escrow_id: u64,
+        member: Address,
+        outcome: DisputeOutcome,
+    ) -> Result<(), EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)?;
+        let dispute =
+            Self::get_dispute_resolution(&env, escrow_id).ok_or(EscrowError::NoDispute)?;
+        let panel =
+            Self::deciding_panel(&env, escrow_id, &dispute).ok_or(EscrowError::Unauthorized)?;
+
+        if !panel.members.contains(&member) {
+            return Err(EscrowError::Unauthorized);
+        }
+        if !dispute.is_active {
+            return Err(EscrowError::NoDispute);
+        }
+        if env.ledger().timestamp() >= dispute.voting_ends
+            || dispute.ruling != DisputeOutcomeOption::None
+        {
+            return Err(EscrowError::VotingClosed);
+        }
+        // Members vote on the evidence once the parties have submitted it
+        if env.ledger().timestamp() < dispute.evidence_ends {
+            return Err(EscrowError::EvidenceOpen);
+        }
+        if !Self::valid_outcome(&outcome) {
+            return Err(EscrowError::InvalidAmount);
+        }
+
+        member.require_auth();
+
+        let mut votes = Self::get_votes(&env, escrow_id);
+        votes.set(member.clone(), outcome.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::DisputeVotes(escrow_id), &votes);
+
+        env.events()
+            .publish((symbol_short!("vote"), escrow_id, member), outcome);
+
+        Ok(())
+    }
+
+    // Tally the panel's votes and rule for the winning outcome, or the
+    // fallback when no outcome carries. Anyone may call this once the voting
+    // window has closed or every member has voted.
+    pub fn tally_votes(env: Env, escrow_id: u64) -> Result<DisputeOutcome, EscrowError> {
+        let mut escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        let dispute =
+            Self::get_dispute_resolution(&env, escrow_id).ok_or(EscrowError::NoDispute)?;
+        let panel =
+            Self::deciding_panel(&env, escrow_id, &dispute).ok_or(EscrowError::NoDispute)?;
+
+        if !dispute.is_active {
+            return Err(EscrowError::NoDispute);
+        }
+        Self::check_ready_to_rule(&env, &dispute)?;
+
+        let votes = Self::get_votes(&env, escrow_id);
+        if env.ledger().timestamp() < dispute.voting_ends && votes.len() < panel.members.len() {
+            return Err(EscrowError::VotingOpen);
+        }
+
+        let outcome = Self::panel_decision(&env, &panel, &votes);
+        Self::rule(&env, &mut escrow, dispute, outcome.clone())?;
+
+        env.events().publish(
+            (symbol_short!("panel_res"), escrow_id),
+            (outcome.clone(), votes.len()),
+        );
+
+        Ok(outcome)
+    }
+
+    pub fn get_dispute(env: Env, escrow_id: u64) -> Result<DisputeProcess, EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)?;
+        Self::get_dispute_resolution(&env, escrow_id).ok_or(EscrowError::NoDispute)
+    }
+
+    pub fn get_dispute_terms(
+        env: Env,
+        escrow_id: u64,
+    ) -> Result<Option<DisputeTerms>, EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)?;
+        Ok(Self::get_terms(&env, escrow_id))
+    }
+
+    // Evidence submitted so far in the escrow's current dispute
+    pub fn get_evidence(env: Env, escrow_id: u64) -> Result<Vec<Evidence>, EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)?;
+        Ok(Self::get_evidence_list(&env, escrow_id))
+    }
+
+    // Every amendment proposed for the escrow, oldest version first
+    pub fn get_amendments(env: Env, escrow_id: u64) -> Result<Vec<Amendment>, EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)?;
+        Ok(Self::get_amendment_list(&env, escrow_id))
+    }
+
+    pub fn get_arbitration_panel(
+        env: Env,
+        escrow_id: u64,
+    ) -> Result<Option<ArbitrationPanel>, EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)?;
+        Ok(Self::get_panel(&env, escrow_id))
+    }
+
+    // Votes cast so far on the escrow's current dispute
+    pub fn get_dispute_votes(
+        env: Env,
+        escrow_id: u64,
+    ) -> Result<Map<Address, DisputeOutcome>, EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)?;
+        Ok(Self::get_votes(&env, escrow_id))
+    }
+
+    pub fn get_escrow(env: Env, escrow_id: u64) -> Result<Escrow, EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)
+    }
+
+    pub fn get_escrow_status(env: Env, escrow_id: u64) -> Result<EscrowStatus, EscrowError> {
+        Ok(Self::get_escrow_by_id(&env, escrow_id)?.status)
+    }
+
+    // Amount the contract holds for an escrow in custody mode
+    pub fn get_held_balance(env: Env, escrow_id: u64) -> Result<i128, EscrowError> {
+        Ok(Self::get_escrow_by_id(&env, escrow_id)?.held)
+    }
+
+    // Amount deposited so far and the amount still outstanding
+    pub fn get_funding(env: Env, escrow_id: u64) -> Result<(i128, i128), EscrowError> {
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        Ok((escrow.funded, escrow.amount - escrow.funded))
+    }
+
+    // Other assets locked in the escrow and how much of each has moved
+    pub fn get_basket(env: Env, escrow_id: u64) -> Result<Vec<BasketLeg>, EscrowError> {
+        Ok(Self::get_escrow_by_id(&env, escrow_id)?.legs)
+    }
+
+    pub fn get_fee_schedule(env: Env, escrow_id: u64) -> Result<Option<FeeSchedule>, EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)?;
+        Ok(Self::get_fees(&env, escrow_id))
+    }
+
+    // Fees collected so far in a token
+    pub fn get_fee_balance(env: Env, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::FeeBalance(token))
+            .unwrap_or(0)
+    }
+
+    // Part of the escrow amount that has not been paid out yet
+    pub fn get_remaining_balance(env: Env, escrow_id: u64) -> Result<i128, EscrowError> {
+        Ok(Self::remaining(&Self::get_escrow_by_id(&env, escrow_id)?))
+    }
+
+    pub fn get_release_conditions(env: Env, escrow_id: u64) -> Result<Vec<Condition>, EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)?;
+        Ok(Self::get_conditions(&env, escrow_id))
+    }
+
+    // Signers currently approving a MultiSig condition
+    pub fn get_condition_approvals(
+        env: Env,
+        escrow_id: u64,
+        condition_index: u32,
+    ) -> Result<Vec<Address>, EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)?;
+        Ok(Self::get_approvals(&env, escrow_id, condition_index))
+    }
+
+    // IDs of all escrows a depositor has created
+    pub fn get_depositor_escrows(env: Env, depositor: Address) -> Vec<u64> {
+        Self::get_index(&env, DataKey::DepositorEscrows(depositor))
+    }
+
+    // IDs of all escrows paying out to a beneficiary
+    pub fn get_beneficiary_escrows(env: Env, beneficiary: Address) -> Vec<u64> {
+        Self::get_index(&env, DataKey::BeneficiaryEscrows(beneficiary))
+    }
+
+    // IDs of all escrows an arbitrator oversees
+    pub fn get_arbitrator_escrows(env: Env, arbitrator: Address) -> Vec<u64> {
+        Self::get_index(&env, DataKey::ArbitratorEscrows(arbitrator))
+    }
+
+    // Set the fee schedule new escrows are created with, or clear it
+    pub fn set_fee_schedule(
+        env: Env,
+        caller: Address,
+        fees: Option<FeeSchedule>,
+    ) -> Result<(), EscrowError> {
+        let admin = Self::get_admin(&env)?;
+
+        if caller != admin {
+            return Err(EscrowError::Unauthorized);
+        }
+        if let Some(fees) = &fees {
+            if fees.platform_fee_bps < 0
+                || fees.platform_fee_bps > 10000
+                || fees.arbitrator_fee < 0
+                || fees.dispute_surcharge < 0
+            {
+                return Err(EscrowError::InvalidAmount);
+            }
+        }
+
+        caller.require_auth();
+
+        match fees {
+            Some(fees) => env.storage().persistent().set(&DataKey::FeeSchedule, &fees),
+            None => env.storage().persistent().remove(&DataKey::FeeSchedule),
+        }
+
+        Ok(())
+    }
+
+    pub fn set_claim_grace_period(
+        env: Env,
+        caller: Address,
+        grace_period: u64,
+    ) -> Result<(), EscrowError> {
+        let admin = Self::get_admin(&env)?;
+
+        if caller != admin {
+            return Err(EscrowError::Unauthorized);
+        }
+
+        caller.require_auth();
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::ClaimGracePeriod, &grace_period);
+
+        Ok(())
+    }
+
+    pub fn set_approval_manager(
+        env: Env,
+        caller: Address,
+        manager: Option<Address>,
+    ) -> Result<(), EscrowError> {
+        let admin = Self::get_admin(&env)?;
+
+        if caller != admin {
+            return Err(EscrowError::Unauthorized);
+        }
+
+        caller.require_auth();
+
+        match manager {
+            Some(manager) => env
+                .storage()
+                .persistent()
+                .set(&DataKey::ApprovalManager, &manager),
+            None => env.storage().persistent().remove(&DataKey::ApprovalManager),
+        }
+
+        Ok(())
+    }
+
+    // Message the attester of a signed-attestation condition has to sign
+    pub fn get_attestation_message(
+        env: Env,
+        escrow_id: u64,
+        condition_index: u32,
+    ) -> Result<Bytes, EscrowError> {
+        Self::get_escrow_by_id(&env, escrow_id)?;
+        let condition = Self::get_conditions(&env, escrow_id)
+            .get(condition_index)
+            .ok_or(EscrowError::InvalidCondition)?;
+        let ConditionTrigger::Attestation(_, template) = condition.trigger else {
+            return Err(EscrowError::InvalidCondition);
+        };
+        Ok(Self::attestation_message(
+            template,
+            escrow_id,
+            condition_index,
+        ))
+    }
+
+    // Payload the approval request for releasing an escrow has to be proposed with
+    pub fn get_release_payload(env: Env, escrow_id: u64) -> Result<BytesN<32>, EscrowError> {
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        Ok(Self::release_payload(
+            &env,
+            escrow_id,
+            &escrow.beneficiary,
+            Self::remaining(&escrow),
+        ))
+    }
+
+    // Payload the approval request for paying out a milestone has to be proposed with
+    pub fn get_milestone_payload(
+        env: Env,
+        escrow_id: u64,
+        condition_index: u32,
+    ) -> Result<BytesN<32>, EscrowError> {
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        let condition = Self::get_conditions(&env, escrow_id)
+            .get(condition_index)
+            .ok_or(EscrowError::InvalidCondition)?;
+        Ok(Self::milestone_payload(
+            &env,
+            escrow_id,
+            &escrow.beneficiary,
+            condition_index,
+            Self::milestone_amount(&escrow, &condition.payout)?,
+        ))
+    }
+
+    // Payload the approval request for paying out a dispute outcome to the
+    // beneficiary has to be proposed with
+    pub fn get_outcome_payload(
+        env: Env,
+        escrow_id: u64,
+        outcome: DisputeOutcome,
+    ) -> Result<BytesN<32>, EscrowError> {
+        let escrow = Self::get_escrow_by_id(&env, escrow_id)?;
+        let (_, _, beneficiary_amount, _) = Self::split_outcome(&env, &escrow, &outcome)?;
+        Ok(Self::release_payload(
+            &env,
+            escrow_id,
+            &escrow.beneficiary,
+            beneficiary_amount,
+        ))
+    }
+
+    // Helper functions
+    #[allow(dead_code)]
+    pub fn get_admin(env: &Env) -> Result<Address, EscrowError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .ok_or(EscrowError::NotInitialized)
+    }
+
+    fn release_payload(
+        env: &Env,
+        escrow_id: u64,
+        beneficiary: &Address,
+        amount: i128,
+    ) -> BytesN<32> {
+        let action = (
+            Symbol::new(env, "release_funds"),
+            escrow_id,
+            beneficiary.clone(),
+            amount,
+        );
+        env.crypto().sha256(&action.to_xdr(env)).to_bytes()
+    }
+
+    fn milestone_payload(
+        env: &Env,
+        escrow_id: u64,
+        beneficiary: &Address,
+        condition_index: u32,
+        amount: i128,
+    ) -> BytesN<32> {
+        let action = (
+            Symbol::new(env, "pay_milestone"),
+            escrow_id,
+            beneficiary.clone(),
+            condition_index,
+            amount,
+        );
+        env.crypto().sha256(&action.to_xdr(env)).to_bytes()
+    }
+
+    // The template followed by the escrow ID and the condition index, so an
+    // attestation cannot be replayed against another escrow or condition
+    fn attestation_message(template: Bytes, escrow_id: u64, condition_index: u32) -> Bytes {
+        let mut message = template;
+        message.extend_from_array(&escrow_id.to_be_bytes());
+        message.extend_from_array(&condition_index.to_be_bytes());
+        message
+    }
+
+    fn consume_approval(env: &Env, payload: BytesN<32>) -> Result<(), EscrowError> {
+        let manager: Option<Address> = env.storage().persistent().get(&DataKey::ApprovalManager);
+        if let Some(manager) = manager {
+            let result = env.try_invoke_contract::<u64, soroban_sdk::Error>(
+                &manager,
+                &Symbol::new(env, "consume"),
+                Vec::from_array(
+                    env,
+                    [
+                        env.current_contract_address().into_val(env),
+                        payload.into_val(env),
+                    ],
+                ),
+            );
+            if !matches!(result, Ok(Ok(_))) {
+                return Err(EscrowError::ApprovalRequired);
+            }
+        }
+        Ok(())
+    }
+
+    // Pay out of the escrow. Every basket leg pays out the same share of its
+    // own amount; legs are settled on the running total so rounding never
+    // strands part of a leg.
+    fn pay_out(env: &Env, escrow: &mut Escrow, to: &Address, amount: i128) {
+        Self::transfer_out(
+            env,
+            &escrow.deposit_account,
+            &escrow.asset.token,
+            to,
+            amount,
+        );
+        if escrow.deposit_account.is_none() {
+            escrow.held -= amount;
+        }
+        escrow.paid_out += amount;
+
+        let mut legs = escrow.legs.clone();
+        for index in 0..legs.len() {
+            let mut leg = legs.get_unchecked(index);
+            let due = leg.amount * escrow.paid_out / escrow.amount;
+            Self::transfer_out(
+                env,
+                &escrow.deposit_account,
+                &leg.asset.token,
+                to,
+                due - leg.paid_out,
+            );
+            leg.paid_out = due;
+            legs.set(index, leg);
+        }
+        escrow.legs = legs;
+    }
+
+    // Pay whatever is left out to the beneficiary and complete the escrow
+    fn release(env: &Env, escrow: &mut Escrow) -> Result<(), EscrowError> {
+        // Releases may require a maker-checker approval for this escrow, beneficiary and amount
+        let (beneficiary, amount) = (escrow.beneficiary.clone(), Self::remaining(escrow));
+        Self::consume_approval(
+            env,
+            Self::release_payload(env, escrow.id, &beneficiary, amount),
+        )?;
+
+        Self::pay_beneficiary(env, escrow, amount);
+        escrow.released += amount;
+
+        escrow.status = EscrowStatus::Released;
+        Self::save_escrow(env, escrow);
+
+        env.events()
+            .publish((symbol_short!("released"), escrow.id, beneficiary), amount);
+
+        Ok(())
+    }
+
+    // Pay the beneficiary less the platform fee, which goes to the fee collector
+    fn pay_beneficiary(env: &Env, escrow: &mut Escrow, amount: i128) {
+        let fee =
+            Self::get_fees(env, escrow.id).map_or(0, |fees| amount * fees.platform_fee_bps / 10000);
+        let beneficiary = escrow.beneficiary.clone();
+        Self::pay_out(env, escrow, &beneficiary, amount - fee);
+        Self::pay_fee(env, escrow, fee);
+    }
+
+    fn pay_fee(env: &Env, escrow: &mut Escrow, fee: i128) {
+        let Some(fees) = Self::get_fees(env, escrow.id) else {
+            return;
+        };
+        if fee <= 0 {
+            return;
+        }
+        let legs = escrow.legs.clone();
+        Self::pay_out(env, escrow, &fees.fee_collector, fee);
+        Self::record_fee(env, escrow.id, &escrow.asset.token, fee);
+
+        // Basket legs pay their share of the fee in their own tokens
+        for (before, after) in legs.iter().zip(escrow.legs.iter()) {
+            let leg_fee = after.paid_out - before.paid_out;
+            if leg_fee > 0 {
+                Self::record_fee(env, escrow.id, &after.asset.token, leg_fee);
+            }
+        }
+    }
+
+    // Track fees collected per token for accounting purposes
+    fn record_fee(env: &Env, escrow_id: u64, token: &Address, fee: i128) {
+        let fee_balance_key = DataKey::FeeBalance(token.clone());
+        let current_fee_balance: i128 = env
+            .storage()
+            .persistent()
+            .get(&fee_balance_key)
+            .unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&fee_balance_key, &(current_fee_balance + fee));
+
+        env.events()
+            .publish((symbol_short!("fee"), escrow_id, token.clone()), fee);
+    }
+
+    // Deposits go to the deposit account, or to the contract when it holds
+    // the funds itself
+    fn transfer_in(
+        env: &Env,
+        deposit_account: &Option<Address>,
+        token: &Address,
+        from: &Address,
+        amount: i128,
+    ) {
+        if amount <= 0 {
+            return;
+        }
+        let destination = match deposit_account {
+            Some(deposit_account) => deposit_account.clone(),
+            None => env.current_contract_address(),
+        };
+        Client::new(env, token).transfer(from, &destination, &amount);
+    }
+
+    // Held funds are sent directly; funds parked in a deposit account are
+    // pulled with the contract as spender, which needs an allowance from
+    // that account.
+    fn transfer_out(
+        env: &Env,
+        deposit_account: &Option<Address>,
+        token: &Address,
+        to: &Address,
+        amount: i128,
+    ) {
+        if amount <= 0 {
+            return;
+        }
+        let token_client = Client::new(env, token);
+        match deposit_account {
+            Some(deposit_account) => token_client.transfer_from(
+                &env.current_contract_address(),
+                deposit_account,
+                to,
+                &amount,
+            ),
+            None => token_client.transfer(&env.current_contract_address(), to, &amount),
+        }
+    }
+
+    // Pay out a dispute outcome and close the dispute. The outcome only
+    // applies to what has not been released yet.
+    fn execute_outcome(
+        env: &Env,
+        escrow: &mut Escrow,
+        mut dispute: DisputeProcess,
+        outcome: DisputeOutcome,
+    ) -> Result<(), EscrowError> {
+        let depositor = escrow.depositor.clone();
+        let (arbitrator_fee, surcharge, beneficiary_amount, depositor_amount) =
+            Self::split_outcome(env, escrow, &outcome)?;
+
+        // Paying the beneficiary needs the same maker-checker approval as a release
+        if beneficiary_amount > 0 {
+            let beneficiary = escrow.beneficiary.clone();
+            Self::consume_approval(
+                env,
+                Self::release_payload(env, escrow.id, &beneficiary, beneficiary_amount),
+            )?;
+        }
+
+        Self::pay_fee(env, escrow, arbitrator_fee);
+        Self::pay_fee(env, escrow, surcharge);
+        match outcome {
+            DisputeOutcome::ReleaseToBeneficiary => {
+                Self::pay_beneficiary(env, escrow, beneficiary_amount);
             }
             DisputeOutcome::RefundToDepositor => {
-                token_client.transfer_from(
-                    &env.current_contract_address(),
-                    &deposit_account,
-                    &depositor,
-                    &amount,
+                Self::pay_out(env, escrow, &depositor, depositor_amount);
+            }
+            DisputeOutcome::PartialRelease(_) => {
+                Self::pay_beneficiary(env, escrow, beneficiary_amount);
+                Self::pay_out(env, escrow, &depositor, depositor_amount);
+            }
+        }
+
+        dispute.is_active = false;
+        dispute.outcome = DisputeOutcomeOption::Some(outcome);
+        env.storage()
+            .persistent()
+            .set(&DataKey::DisputeResolution(escrow.id), &dispute);
+        escrow.status = EscrowStatus::Resolved;
+        Self::save_escrow(env, escrow);
+
+        Ok(())
+    }
+
+    // Split the unreleased funds under a dispute outcome into the arbitrator
+    // fee, the surcharge and what the beneficiary and the depositor receive
+    fn split_outcome(
+        env: &Env,
+        escrow: &Escrow,
+        outcome: &DisputeOutcome,
+    ) -> Result<(i128, i128, i128, i128), EscrowError> {
+        if let DisputeOutcome::PartialRelease(basis_points) = outcome {
+            if *basis_points <= 0 || *basis_points >= 10000 {
+                return Err(EscrowError::InvalidAmount);
+            }
+        }
+
+        // The arbitrator fee comes out of the disputed funds first
+        let arbitrator_fee = Self::get_fees(env, escrow.id)
+            .map_or(0, |fees| fees.arbitrator_fee)
+            .min(Self::remaining(escrow));
+        let amount = Self::remaining(escrow) - arbitrator_fee;
+
+        let (mut beneficiary_amount, mut depositor_amount) = match outcome {
+            DisputeOutcome::ReleaseToBeneficiary => (amount, 0),
+            DisputeOutcome::RefundToDepositor => (0, amount),
+            DisputeOutcome::PartialRelease(basis_points) => {
+                let beneficiary_amount = (amount * *basis_points) / 10000;
+                (beneficiary_amount, amount - beneficiary_amount)
+            }
+        };
+
+        // The party the outcome goes against is charged the surcharge. It is
+        // taken out of the disputed funds before the winner is paid, from the
+        // loser's own share first when the outcome leaves them one
+        let surcharge = Self::get_fees(env, escrow.id)
+            .map_or(0, |fees| fees.dispute_surcharge)
+            .min(amount);
+        let surcharge = if beneficiary_amount < depositor_amount {
+            let from_share = surcharge.min(beneficiary_amount);
+            beneficiary_amount -= from_share;
+            depositor_amount -= surcharge - from_share;
+            surcharge
+        } else if depositor_amount < beneficiary_amount {
+            let from_share = surcharge.min(depositor_amount);
+            depositor_amount -= from_share;
+            beneficiary_amount -= surcharge - from_share;
+            surcharge
+        } else {
+            0
+        };
+
+        Ok((
+            arbitrator_fee,
+            surcharge,
+            beneficiary_amount,
+            depositor_amount,
+        ))
+    }
+
+    // Before an appeal, a ruling under dispute terms waits out the appeal
+    // window; otherwise it is paid out right away
+    fn rule(
+        env: &Env,
+        escrow: &mut Escrow,
+        mut dispute: DisputeProcess,
+        outcome: DisputeOutcome,
+    ) -> Result<(), EscrowError> {
+        match Self::get_terms(env, escrow.id) {
+            Some(terms) if !dispute.appealed => {
+                if !Self::valid_outcome(&outcome) {
+                    return Err(EscrowError::InvalidAmount);
+                }
+                dispute.ruling = DisputeOutcomeOption::Some(outcome.clone());
+                dispute.appeal_ends = env.ledger().timestamp() + terms.appeal_window;
+                env.storage()
+                    .persistent()
+                    .set(&DataKey::DisputeResolution(escrow.id), &dispute);
+
+                env.events().publish(
+                    (symbol_short!("ruling"), escrow.id),
+                    (outcome, dispute.appeal_ends),
                 );
+                Ok(())
             }
+            _ => Self::execute_outcome(env, escrow, dispute, outcome),
+        }
+    }
+
+    // Rulings wait for the evidence period to end, and only one can be pending
+    fn check_ready_to_rule(env: &Env, dispute: &DisputeProcess) -> Result<(), EscrowError> {
+        if env.ledger().timestamp() < dispute.evidence_ends {
+            return Err(EscrowError::EvidenceOpen);
+        }
+        if dispute.ruling != DisputeOutcomeOption::None {
+            return Err(EscrowError::AppealOpen);
+        }
+        Ok(())
+    }
+
+    // An appealed dispute goes to the appeal panel
+    fn deciding_panel(
+        env: &Env,
+        escrow_id: u64,
+        dispute: &DisputeProcess,
+    ) -> Option<ArbitrationPanel> {
+        if dispute.appealed {
+            Self::get_terms(env, escrow_id).map(|terms| terms.appeal_panel)
+        } else {
+            Self::get_panel(env, escrow_id)
+        }
+    }
+
+    fn valid_panel(panel: &ArbitrationPanel) -> bool {
+        !panel.members.is_empty() && panel.voting_period > 0 && Self::valid_outcome(&panel.fallback)
+    }
+
+    fn valid_outcome(outcome: &DisputeOutcome) -> bool {
+        match outcome {
             DisputeOutcome::PartialRelease(basis_points) => {
-                if basis_points <= 0 || basis_points >= 10000 {
+                *basis_points > 0 && *basis_points < 10000
+            }
+            _ => true,
+        }
+    }
+
+    // The outcome with the most votes wins if it has the share of the whole
+    // panel the rule asks for; ties and short counts fall back
+    fn panel_decision(
+        env: &Env,
+        panel: &ArbitrationPanel,
+        votes: &Map<Address, DisputeOutcome>,
+    ) -> DisputeOutcome {
+        let mut tally: Vec<(DisputeOutcome, u32)> = Vec::new(env);
+        for outcome in votes.values() {
+            match tally.iter().position(|(counted, _)| counted == outcome) {
+                Some(index) => {
+                    let (counted, count) = tally.get_unchecked(index as u32);
+                    tally.set(index as u32, (counted, count + 1));
+                }
+                None => tally.push_back((outcome, 1)),
+            }
+        }
+
+        let mut winner = None;
+        let mut best = 0;
+        let mut tied = false;
+        for (outcome, count) in tally.iter() {
+            if count > best {
+                winner = Some(outcome);
+                best = count;
+                tied = false;
+            } else if count == best {
+                tied = true;
+            }
+        }
+
+        let members = panel.members.len();
+        let carried = match panel.rule {
+            DecisionRule::Majority => best * 2 > members,
+            DecisionRule::Supermajority => best * 3 >= members * 2,
+        };
+        match winner {
+            Some(outcome) if carried && !tied => outcome,
+            _ => panel.fallback.clone(),
+        }
+    }
+
+    fn check_amendment(
+        env: &Env,
+        escrow: &Escrow,
+        change: &AmendmentChange,
+    ) -> Result<(), EscrowError> {
+        if escrow.status != EscrowStatus::Initialized
+            && escrow.status != EscrowStatus::Funded
+            && escrow.status != EscrowStatus::ConditionsMet
+        {
+            return Err(EscrowError::InvalidStatus);
+        }
+
+        match change {
+            AmendmentChange::Amount(amount) => {
+                // Basket legs are sized against the amount they were added with
+                if !escrow.legs.is_empty() {
+                    return Err(EscrowError::InvalidAmendment);
+                }
+                if *amount <= escrow.released {
                     return Err(EscrowError::InvalidAmount);
                 }
-                let beneficiary_amount = (amount * basis_points) / 10000;
-                let depositor_amount = amount - beneficiary_amount;
-
-                token_client.transfer_from(
-                    &env.current_contract_address(),
-                    &deposit_account,
-                    &beneficiary,
-                    &beneficiary_amount,
+                // Milestones must still fit in the new amount
+                let amended = Escrow {
+                    amount: *amount,
+                    ..escrow.clone()
+                };
+                let committed: i128 = Self::get_conditions(env, escrow.id)
+                    .iter()
+                    .map(|c| Self::milestone_amount(&amended, &c.payout).unwrap_or(0))
+                    .sum();
+                if committed > *amount {
+                    return Err(EscrowError::InvalidAmount);
+                }
+            }
+            AmendmentChange::ExtendTimeout(timeout_time) => {
+                if *timeout_time <= escrow.timeout_time {
+                    return Err(EscrowError::InvalidAmendment);
+                }
+            }
+            AmendmentChange::ClaimGracePeriod(grace_period) => {
+                if *grace_period == escrow.claim_grace_period {
+                    return Err(EscrowError::InvalidAmendment);
+                }
+            }
+            AmendmentChange::Arbitrator(arbitrator) => {
+                if *arbitrator == escrow.arbitrator
+                    || *arbitrator == escrow.depositor
+                    || *arbitrator == escrow.beneficiary
+                {
+                    return Err(EscrowError::InvalidAmendment);
+                }
+            }
+            AmendmentChange::RemoveCondition(index) => {
+                let condition = Self::get_conditions(env, escrow.id)
+                    .get(*index)
+                    .ok_or(EscrowError::InvalidCondition)?;
+                if condition.is_fulfilled {
+                    return Err(EscrowError::AlreadyFulfilled);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_amendment(
+        env: &Env,
+        escrow: &mut Escrow,
+        approver: &Address,
+        change: &AmendmentChange,
+    ) {
+        match change {
+            AmendmentChange::Amount(amount) => {
+                let depositor = escrow.depositor.clone();
+                // An escrow still being funded just takes more or fewer
+                // installments; a funded one is topped up right away
+                if *amount > escrow.funded && escrow.status != EscrowStatus::Initialized {
+                    if *approver != depositor {
+                        depositor.require_auth();
+                    }
+                    let top_up = *amount - escrow.funded;
+                    Self::transfer_in(
+                        env,
+                        &escrow.deposit_account,
+                        &escrow.asset.token,
+                        &depositor,
+                        top_up,
+                    );
+                    if escrow.deposit_account.is_none() {
+                        escrow.held += top_up;
+                    }
+                    escrow.funded = *amount;
+                } else if *amount < escrow.funded {
+                    Self::pay_out(env, escrow, &depositor, escrow.funded - *amount);
+                    escrow.funded = *amount;
+                }
+                escrow.amount = *amount;
+
+                if escrow.status == EscrowStatus::Initialized && escrow.funded == escrow.amount {
+                    escrow.status = EscrowStatus::Funded;
+                }
+            }
+            AmendmentChange::ExtendTimeout(timeout_time) => {
+                escrow.timeout_time = *timeout_time;
+            }
+            AmendmentChange::ClaimGracePeriod(grace_period) => {
+                escrow.claim_grace_period = *grace_period;
+            }
+            AmendmentChange::Arbitrator(arbitrator) => {
+                Self::remove_from_index(
+                    env,
+                    DataKey::ArbitratorEscrows(escrow.arbitrator.clone()),
+                    escrow.id,
                 );
-                token_client.transfer_from(
-                    &env.current_contract_address(),
-                    &deposit_account,
-                    &depositor,
-                    &depositor_amount,
+                Self::add_to_index(
+                    env,
+                    DataKey::ArbitratorEscrows(arbitrator.clone()),
+                    escrow.id,
                 );
+                escrow.arbitrator = arbitrator.clone();
+            }
+            AmendmentChange::RemoveCondition(index) => {
+                let mut conditions = Self::get_conditions(env, escrow.id);
+                conditions.remove(*index);
+
+                // MultiSig approvals are keyed by index and move down with
+                // the conditions after the removed one
+                for i in *index..conditions.len() {
+                    env.storage().persistent().set(
+                        &DataKey::ConditionApprovals(escrow.id, i),
+                        &Self::get_approvals(env, escrow.id, i + 1),
+                    );
+                }
+                env.storage()
+                    .persistent()
+                    .remove(&DataKey::ConditionApprovals(escrow.id, conditions.len()));
+
+                // Dropping the last open condition of a funded escrow meets
+                // them all, even when it was the only condition left
+                if escrow.status == EscrowStatus::Funded {
+                    Self::save_conditions(env, escrow, &conditions);
+                } else {
+                    env.storage()
+                        .persistent()
+                        .set(&DataKey::ReleaseConditions(escrow.id), &conditions);
+                }
             }
         }
+    }
 
-        let mut dispute = dispute.clone();
-        dispute.is_active = false;
-        dispute.outcome = DisputeOutcomeOption::Some(outcome);
+    // Mark a condition fulfilled. A milestone releases its share to the
+    // beneficiary right away.
+    fn fulfill_condition(
+        env: &Env,
+        escrow: &mut Escrow,
+        conditions: &mut Vec<Condition>,
+        index: u32,
+    ) -> Result<(), EscrowError> {
+        let mut condition = conditions.get(index).ok_or(EscrowError::InvalidCondition)?;
+        condition.is_fulfilled = true;
+        conditions.set(index, condition.clone());
+
+        let milestone_amount = Self::milestone_amount(escrow, &condition.payout)?;
+        if milestone_amount > 0 {
+            // Milestone payouts need the same maker-checker approval as releases
+            let beneficiary = escrow.beneficiary.clone();
+            Self::consume_approval(
+                env,
+                Self::milestone_payload(env, escrow.id, &beneficiary, index, milestone_amount),
+            )?;
+            Self::pay_beneficiary(env, escrow, milestone_amount);
+            escrow.released += milestone_amount;
+
+            env.events().publish(
+                (symbol_short!("milestone"), escrow.id, beneficiary),
+                (index, milestone_amount),
+            );
+        }
+
+        Ok(())
+    }
+
+    // Store updated conditions. Once every condition is met the escrow is
+    // complete if the milestones already paid out everything.
+    fn save_conditions(env: &Env, escrow: &mut Escrow, conditions: &Vec<Condition>) {
         env.storage()
             .persistent()
-            .set(&DataKey::DisputeResolution, &Some(dispute));
+            .set(&DataKey::ReleaseConditions(escrow.id), conditions);
+
+        if conditions.iter().all(|c| c.is_fulfilled) {
+            escrow.status = if Self::remaining(escrow) == 0 {
+                EscrowStatus::Released
+            } else {
+                EscrowStatus::ConditionsMet
+            };
+            escrow.conditions_met_at = env.ledger().timestamp();
+        }
+        Self::save_escrow(env, escrow);
+    }
+
+    // Time-based conditions need an unlock point, oracle conditions an oracle,
+    // MultiSig conditions distinct signers and a reachable threshold,
+    // hash-time locks a timelock and attestations a message template; manual
+    // conditions must not have a trigger
+    fn valid_trigger(condition: &Condition) -> bool {
+        match (&condition.condition_type, &condition.trigger) {
+            (
+                ConditionType::TimeBased,
+                ConditionTrigger::UnlockTime(_) | ConditionTrigger::UnlockLedger(_),
+            ) => true,
+            (ConditionType::ExternalOracle, ConditionTrigger::Oracle(_, _)) => true,
+            (ConditionType::MultiSig, ConditionTrigger::MultiSig(signers, threshold)) => {
+                *threshold > 0
+                    && *threshold <= signers.len()
+                    && signers.iter().enumerate().all(|(index, signer)| {
+                        signers.first_index_of(&signer) == Some(index as u32)
+                    })
+            }
+            (ConditionType::HashTimeLock, ConditionTrigger::HashLock(_, timelock)) => *timelock > 0,
+            (ConditionType::SignedAttestation, ConditionTrigger::Attestation(_, template)) => {
+                !template.is_empty()
+            }
+            (ConditionType::ManualVerification, trigger) => *trigger == ConditionTrigger::None,
+            _ => false,
+        }
+    }
+
+    fn is_unlocked(env: &Env, trigger: &ConditionTrigger) -> bool {
+        match trigger {
+            ConditionTrigger::UnlockTime(unlock_time) => env.ledger().timestamp() >= *unlock_time,
+            ConditionTrigger::UnlockLedger(sequence) => env.ledger().sequence() >= *sequence,
+            ConditionTrigger::None => true,
+            ConditionTrigger::Oracle(_, _)
+            | ConditionTrigger::MultiSig(_, _)
+            | ConditionTrigger::HashLock(_, _)
+            | ConditionTrigger::Attestation(_, _) => false,
+        }
+    }
+
+    // Whether a condition can be fulfilled without the arbitrator right now
+    fn is_triggered(env: &Env, trigger: &ConditionTrigger) -> bool {
+        match trigger {
+            // MultiSig conditions are fulfilled as the approvals come in and
+            // attestations as they are submitted
+            ConditionTrigger::None
+            | ConditionTrigger::MultiSig(_, _)
+            | ConditionTrigger::Attestation(_, _) => false,
+            ConditionTrigger::Oracle(oracle, key) => Self::oracle_attests(env, oracle, key),
+            _ => Self::is_unlocked(env, trigger),
+        }
+    }
+
+    // Whether an unfulfilled hashlock of the escrow has passed its timelock
+    fn hashlock_expired(env: &Env, escrow: &Escrow) -> bool {
+        Self::get_conditions(env, escrow.id).iter().any(|c| {
+            matches!(c.trigger, ConditionTrigger::HashLock(_, timelock)
+                if !c.is_fulfilled && env.ledger().timestamp() >= timelock)
+        })
+    }
+
+    // Ask the oracle contract to attest a key. An oracle that fails or does
+    // not implement `attest(key) -> bool` counts as not attesting.
+    fn oracle_attests(env: &Env, oracle: &Address, key: &Bytes) -> bool {
+        let result = env.try_invoke_contract::<bool, soroban_sdk::Error>(
+            oracle,
+            &Symbol::new(env, "attest"),
+            Vec::from_array(env, [key.into_val(env)]),
+        );
+        matches!(result, Ok(Ok(true)))
+    }
+
+    // Amount a condition releases when verified
+    fn milestone_amount(escrow: &Escrow, payout: &ConditionPayout) -> Result<i128, EscrowError> {
+        match payout {
+            ConditionPayout::None => Ok(0),
+            ConditionPayout::Amount(amount) if *amount > 0 => Ok(*amount),
+            ConditionPayout::Share(basis_points) if *basis_points > 0 && *basis_points <= 10000 => {
+                Ok((escrow.amount * basis_points) / 10000)
+            }
+            _ => Err(EscrowError::InvalidAmount),
+        }
+    }
+
+    fn remaining(escrow: &Escrow) -> i128 {
+        escrow.amount - escrow.released
+    }
+
+    fn save_escrow(env: &Env, escrow: &Escrow) {
         env.storage()
             .persistent()
-            .set(&DataKey::Status, &EscrowStatus::Resolved);
+            .set(&DataKey::Escrow(escrow.id), escrow);
+    }
 
-        env.events()
-            .publish((symbol_short!("disp_res"), arbitrator), true);
+    fn add_to_index(env: &Env, key: DataKey, escrow_id: u64) {
+        let mut escrow_ids = Self::get_index(env, key.clone());
+        escrow_ids.push_back(escrow_id);
+        env.storage().persistent().set(&key, &escrow_ids);
+    }
 
-        Ok(())
+    fn remove_from_index(env: &Env, key: DataKey, escrow_id: u64) {
+        let mut escrow_ids = Self::get_index(env, key.clone());
+        if let Some(index) = escrow_ids.first_index_of(escrow_id) {
+            escrow_ids.remove(index);
+            env.storage().persistent().set(&key, &escrow_ids);
+        }
     }
 
-    pub fn get_escrow_status(env: Env) -> Result<EscrowStatus, EscrowError> {
-        Self::get_status(&env)
+    fn get_index(env: &Env, key: DataKey) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env))
     }
 
-    // Helper functions
-    #[allow(dead_code)]
-    pub fn get_admin(env: &Env) -> Result<Address, EscrowError> {
+    fn get_escrow_count(env: &Env) -> Result<u64, EscrowError> {
         env.storage()
             .persistent()
-            .get(&DataKey::Admin)
+            .get(&DataKey::EscrowCount)
             .ok_or(EscrowError::NotInitialized)
     }
 
-    fn get_depositor(env: &Env) -> Result<Address, EscrowError> {
+    fn get_escrow_by_id(env: &Env, escrow_id: u64) -> Result<Escrow, EscrowError> {
         env.storage()
             .persistent()
-            .get(&DataKey::Depositor)
-            .ok_or(EscrowError::NotInitialized)
+            .get(&DataKey::Escrow(escrow_id))
+            .ok_or(EscrowError::EscrowNotFound)
     }
 
-    fn get_beneficiary(env: &Env) -> Result<Address, EscrowError> {
+    fn get_approvals(env: &Env, escrow_id: u64, condition_index: u32) -> Vec<Address> {
         env.storage()
             .persistent()
-            .get(&DataKey::Beneficiary)
-            .ok_or(EscrowError::NotInitialized)
+            .get(&DataKey::ConditionApprovals(escrow_id, condition_index))
+            .unwrap_or(Vec::new(env))
     }
 
-    fn get_arbitrator(env: &Env) -> Result<Address, EscrowError> {
+    fn get_conditions(env: &Env, escrow_id: u64) -> Vec<Condition> {
         env.storage()
             .persistent()
-            .get(&DataKey::Arbitrator)
-            .ok_or(EscrowError::NotInitialized)
+            .get(&DataKey::ReleaseConditions(escrow_id))
+            .unwrap_or(Vec::new(env))
     }
 
-    fn get_deposit_account(env: &Env) -> Result<Address, EscrowError> {
+    fn get_amendment_list(env: &Env, escrow_id: u64) -> Vec<Amendment> {
         env.storage()
             .persistent()
-            .get(&DataKey::DepositAccount)
-            .ok_or(EscrowError::NotInitialized)
+            .get(&DataKey::Amendments(escrow_id))
+            .unwrap_or(Vec::new(env))
+    }
+
+    // Amendment history of an escrow together with its open amendment of this version
+    fn get_open_amendment(
+        env: &Env,
+        escrow_id: u64,
+        version: u32,
+    ) -> Result<(Vec<Amendment>, Amendment), EscrowError> {
+        let amendments = Self::get_amendment_list(env, escrow_id);
+        let amendment = version
+            .checked_sub(1)
+            .and_then(|index| amendments.get(index))
+            .filter(|amendment| amendment.status == AmendmentStatus::Proposed)
+            .ok_or(EscrowError::NoAmendment)?;
+        Ok((amendments, amendment))
     }
 
-    fn get_asset(env: &Env) -> Result<Asset, EscrowError> {
+    fn get_panel(env: &Env, escrow_id: u64) -> Option<ArbitrationPanel> {
         env.storage()
             .persistent()
-            .get(&DataKey::Asset)
-            .ok_or(EscrowError::NotInitialized)
+            .get(&DataKey::ArbitrationPanel(escrow_id))
+    }
+
+    fn get_fees(env: &Env, escrow_id: u64) -> Option<FeeSchedule> {
+        env.storage().persistent().get(&DataKey::Fees(escrow_id))
     }
 
-    fn get_amount(env: &Env) -> Result<i128, EscrowError> {
+    fn get_terms(env: &Env, escrow_id: u64) -> Option<DisputeTerms> {
         env.storage()
             .persistent()
-            .get(&DataKey::Amount)
-            .ok_or(EscrowError::NotInitialized)
+            .get(&DataKey::DisputeTerms(escrow_id))
     }
 
-    fn get_timeout_time(env: &Env) -> Result<u64, EscrowError> {
+    fn get_evidence_list(env: &Env, escrow_id: u64) -> Vec<Evidence> {
         env.storage()
             .persistent()
-            .get(&DataKey::TimeoutTime)
-            .ok_or(EscrowError::NotInitialized)
+            .get(&DataKey::DisputeEvidence(escrow_id))
+            .unwrap_or(Vec::new(env))
     }
 
-    fn get_dispute_resolution(env: &Env) -> Result<Option<DisputeProcess>, EscrowError> {
+    fn get_votes(env: &Env, escrow_id: u64) -> Map<Address, DisputeOutcome> {
         env.storage()
             .persistent()
-            .get(&DataKey::DisputeResolution)
-            .ok_or(EscrowError::NotInitialized)
+            .get(&DataKey::DisputeVotes(escrow_id))
+            .unwrap_or(Map::new(env))
     }
 
-    fn get_status(env: &Env) -> Result<EscrowStatus, EscrowError> {
+    fn get_dispute_resolution(env: &Env, escrow_id: u64) -> Option<DisputeProcess> {
         env.storage()
             .persistent()
-            .get(&DataKey::Status)
-            .ok_or(EscrowError::NotInitialized)
+            .get(&DataKey::DisputeResolution(escrow_id))
     }
 }
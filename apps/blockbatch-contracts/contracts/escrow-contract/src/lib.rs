@@ -6,4 +6,7 @@ mod types;
 pub use contract::*;
 
 #[cfg(test)]
-mod test;
\ No newline at end of file
+mod test;
+
+#[cfg(test)]
+mod testutils;
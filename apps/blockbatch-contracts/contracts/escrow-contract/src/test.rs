@@ -1,8 +1,11 @@
 use super::*;
-use crate::testutils::{create_asset, create_condition, create_token_contract, mint_tokens};
+use crate::testutils::{
+    check_balance, create_asset, create_condition, create_token_contract, mint_tokens,
+};
 use crate::types::*;
+use approval_manager::testutils::{approve_action, register_approval_manager};
 use soroban_sdk::testutils::{Address as _, Ledger};
-use soroban_sdk::{token::TokenClient, Address, Env, String};
+use soroban_sdk::{token::TokenClient, vec, Address, Env, String, Vec};
 
 #[cfg(test)]
 mod test_setup {
@@ -11,14 +14,15 @@ mod test_setup {
     pub fn setup_contract(
         e: &Env,
     ) -> (
-        EscrowContractClient,
+        EscrowContractClient<'_>,
         Address,
         Address,
         Address,
         Address,
         Asset,
-        TokenClient,
+        TokenClient<'_>,
         Address,
+        u64,
     ) {
         let admin = Address::generate(e);
         let depositor = Address::generate(e);
@@ -37,12 +41,12 @@ mod test_setup {
 
         mint_tokens(&token_admin, &depositor, &1000000);
 
-        client.initialize(
-            &admin,
+        client.initialize(&admin);
+        let escrow_id = client.create_escrow(
             &depositor,
             &beneficiary,
             &arbitrator,
-            &deposit_account,
+            &Some(deposit_account.clone()),
             &asset,
             &1000,
             &(17280 * 7), // 7 days
@@ -57,6 +61,7 @@ mod test_setup {
             asset,
             token_client,
             contract_id,
+            escrow_id,
         )
     }
 }
@@ -68,29 +73,32 @@ mod test_initialization {
     #[should_panic(expected = "Error(Contract, #1)")]
     fn test_initialize_twice() {
         let env = Env::default();
-        let (client, depositor, beneficiary, arbitrator, deposit_account, asset, _, _) =
+        let (client, _, _, _, _, _, _, _, _) = test_setup::setup_contract(&env);
+        client.initialize(&Address::generate(&env));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #2)")]
+    fn test_create_escrow_invalid_amount() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, deposit_account, asset, _, _, _) =
             test_setup::setup_contract(&env);
-        client.initialize(
-            &Address::generate(&env),
+        client.create_escrow(
             &depositor,
             &beneficiary,
             &arbitrator,
-            &deposit_account,
+            &Some(deposit_account.clone()),
             &asset,
-            &1000,
+            &0,
             &(17280 * 7),
         );
     }
 
     #[test]
-    #[should_panic(expected = "Error(Contract, #2)")]
-    fn test_initialize_invalid_amount() {
+    #[should_panic(expected = "Error(Contract, #11)")]
+    fn test_create_escrow_not_initialized() {
         let env = Env::default();
         let admin = Address::generate(&env);
-        let depositor = Address::generate(&env);
-        let beneficiary = Address::generate(&env);
-        let arbitrator = Address::generate(&env);
-        let deposit_account = Address::generate(&env);
         let (token_address, _) = create_token_contract(&env, &admin);
         let asset = create_asset(&env, &token_address);
         let contract_id = env.register(EscrowContract, ());
@@ -98,14 +106,13 @@ mod test_initialization {
 
         env.mock_all_auths();
 
-        client.initialize(
-            &admin,
-            &depositor,
-            &beneficiary,
-            &arbitrator,
-            &deposit_account,
+        client.create_escrow(
+            &Address::generate(&env),
+            &Address::generate(&env),
+            &Address::generate(&env),
+            &None,
             &asset,
-            &0,
+            &1000,
             &(17280 * 7),
         );
     }
@@ -117,10 +124,10 @@ mod test_deposit {
     #[test]
     fn test_deposit_funds_success() {
         let env = Env::default();
-        let (client, depositor, _, _, deposit_account, _, token_client, _) =
+        let (client, depositor, _, _, deposit_account, _, token_client, _, escrow_id) =
             test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
-        assert_eq!(client.get_escrow_status(), EscrowStatus::Funded);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
         assert_eq!(token_client.balance(&deposit_account), 1000);
     }
 
@@ -128,26 +135,26 @@ mod test_deposit {
     #[should_panic(expected = "Error(Contract, #1)")]
     fn test_deposit_funds_unauthorized() {
         let env = Env::default();
-        let (client, _, _, _, _, _, _, _) = test_setup::setup_contract(&env);
+        let (client, _, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
         let unauthorized = Address::generate(&env);
-        client.deposit_funds(&unauthorized, &1000);
+        client.deposit_funds(&escrow_id, &unauthorized, &1000);
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #2)")]
     fn test_deposit_funds_wrong_amount() {
         let env = Env::default();
-        let (client, depositor, _, _, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &500);
+        let (client, depositor, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1001);
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #3)")]
     fn test_deposit_funds_wrong_status() {
         let env = Env::default();
-        let (client, depositor, _, _, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
-        client.deposit_funds(&depositor, &1000);
+        let (client, depositor, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
     }
 }
 
@@ -157,75 +164,84 @@ mod test_conditions {
     #[test]
     fn test_add_release_condition_success() {
         let env = Env::default();
-        let (client, _, _, arbitrator, _, _, _, _) = test_setup::setup_contract(&env);
+        let (client, _, _, arbitrator, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
         let condition = create_condition(&env, "Test condition", ConditionType::ManualVerification);
-        client.add_release_condition(&arbitrator, &condition);
-        assert_eq!(client.get_escrow_status(), EscrowStatus::Initialized);
+        client.add_release_condition(&escrow_id, &arbitrator, &condition);
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::Initialized
+        );
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #1)")]
     fn test_add_release_condition_unauthorized() {
         let env = Env::default();
-        let (client, _, _, _, _, _, _, _) = test_setup::setup_contract(&env);
+        let (client, _, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
         let unauthorized = Address::generate(&env);
         let condition = create_condition(&env, "Test condition", ConditionType::ManualVerification);
-        client.add_release_condition(&unauthorized, &condition);
+        client.add_release_condition(&escrow_id, &unauthorized, &condition);
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #7)")]
     fn test_add_release_condition_already_fulfilled() {
         let env = Env::default();
-        let (client, _, _, arbitrator, _, _, _, _) = test_setup::setup_contract(&env);
+        let (client, _, _, arbitrator, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
         let mut condition =
             create_condition(&env, "Test condition", ConditionType::ManualVerification);
         condition.is_fulfilled = true;
-        client.add_release_condition(&arbitrator, &condition);
+        client.add_release_condition(&escrow_id, &arbitrator, &condition);
     }
 
     #[test]
     fn test_verify_condition_success() {
         let env = Env::default();
-        let (client, depositor, _, arbitrator, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
+        let (client, depositor, _, arbitrator, _, _, _, _, escrow_id) =
+            test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
         let condition = create_condition(&env, "Test condition", ConditionType::ManualVerification);
-        client.add_release_condition(&arbitrator, &condition);
-        client.verify_condition(&arbitrator, &0);
-        assert_eq!(client.get_escrow_status(), EscrowStatus::ConditionsMet);
+        client.add_release_condition(&escrow_id, &arbitrator, &condition);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::ConditionsMet
+        );
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #1)")]
     fn test_verify_condition_unauthorized() {
         let env = Env::default();
-        let (client, depositor, _, _, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
+        let (client, depositor, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
         let condition = create_condition(&env, "Test condition", ConditionType::ManualVerification);
-        client.add_release_condition(&Address::generate(&env), &condition);
+        client.add_release_condition(&escrow_id, &Address::generate(&env), &condition);
         let unauthorized = Address::generate(&env);
-        client.verify_condition(&unauthorized, &0);
+        client.verify_condition(&escrow_id, &unauthorized, &0);
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #7)")]
     fn test_verify_condition_invalid_index() {
         let env = Env::default();
-        let (client, depositor, _, arbitrator, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
-        client.verify_condition(&arbitrator, &0);
+        let (client, depositor, _, arbitrator, _, _, _, _, escrow_id) =
+            test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #8)")]
     fn test_verify_condition_already_fulfilled() {
         let env = Env::default();
-        let (client, depositor, _, arbitrator, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
+        let (client, depositor, _, arbitrator, _, _, _, _, escrow_id) =
+            test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
         let condition = create_condition(&env, "Test condition", ConditionType::ManualVerification);
-        client.add_release_condition(&arbitrator, &condition);
-        client.verify_condition(&arbitrator, &0);
-        client.verify_condition(&arbitrator, &0);
+        client.add_release_condition(&escrow_id, &arbitrator, &condition);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
     }
 }
 
@@ -244,6 +260,7 @@ mod test_release {
             _,
             token_client,
             contract_id,
+            escrow_id,
         ) = test_setup::setup_contract(&env);
 
         // Approve the contract to spend tokens from deposit_account with a valid ledger
@@ -252,18 +269,18 @@ mod test_release {
         token_client.approve(&deposit_account, &contract_id, &1000000, &live_until_ledger);
 
         // Deposit funds
-        client.deposit_funds(&depositor, &1000);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
 
         // Add and verify condition
         let condition = create_condition(&env, "Test condition", ConditionType::ManualVerification);
-        client.add_release_condition(&arbitrator, &condition);
-        client.verify_condition(&arbitrator, &0);
+        client.add_release_condition(&escrow_id, &arbitrator, &condition);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
 
         // Release funds
-        client.release_funds(&arbitrator);
+        client.release_funds(&escrow_id, &arbitrator);
 
         // Verify results
-        assert_eq!(client.get_escrow_status(), EscrowStatus::Released);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Released);
         assert_eq!(token_client.balance(&beneficiary), 1000);
         assert_eq!(token_client.balance(&deposit_account), 0);
     }
@@ -272,22 +289,172 @@ mod test_release {
     #[should_panic(expected = "Error(Contract, #1)")]
     fn test_release_funds_unauthorized() {
         let env = Env::default();
-        let (client, depositor, _, arbitrator, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
+        let (client, depositor, _, arbitrator, _, _, _, _, escrow_id) =
+            test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
         let condition = create_condition(&env, "Test condition", ConditionType::ManualVerification);
-        client.add_release_condition(&arbitrator, &condition);
-        client.verify_condition(&arbitrator, &0);
+        client.add_release_condition(&escrow_id, &arbitrator, &condition);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
         let unauthorized = Address::generate(&env);
-        client.release_funds(&unauthorized);
+        client.release_funds(&escrow_id, &unauthorized);
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #5)")]
     fn test_release_funds_conditions_not_met() {
         let env = Env::default();
-        let (client, depositor, _, arbitrator, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
-        client.release_funds(&arbitrator);
+        let (client, depositor, _, arbitrator, _, _, _, _, escrow_id) =
+            test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.release_funds(&escrow_id, &arbitrator);
+    }
+}
+
+mod test_release_approval {
+    use super::*;
+
+    #[test]
+    fn test_release_funds_approved() {
+        let env = Env::default();
+        let (
+            client,
+            depositor,
+            beneficiary,
+            arbitrator,
+            deposit_account,
+            asset,
+            token_client,
+            contract_id,
+            escrow_id,
+        ) = test_setup::setup_contract(&env);
+        let admin = client.get_admin();
+        let manager = register_approval_manager(&env, &contract_id);
+        client.set_approval_manager(&admin, &Some(manager.address.clone()));
+
+        token_client.approve(
+            &deposit_account,
+            &contract_id,
+            &1000,
+            &(env.ledger().sequence() + 1000),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        let condition = create_condition(&env, "Test condition", ConditionType::ManualVerification);
+        client.add_release_condition(&escrow_id, &arbitrator, &condition);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+
+        approve_action(
+            &env,
+            &manager,
+            &contract_id,
+            &client.get_release_payload(&escrow_id),
+        );
+        client.release_funds(&escrow_id, &arbitrator);
+
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Released);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #12)")]
+    fn test_release_funds_without_approval() {
+        let env = Env::default();
+        let (client, depositor, _, arbitrator, _, _, _, contract_id, escrow_id) =
+            test_setup::setup_contract(&env);
+        let admin = client.get_admin();
+        let manager = register_approval_manager(&env, &contract_id);
+        client.set_approval_manager(&admin, &Some(manager.address));
+
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        let condition = create_condition(&env, "Test condition", ConditionType::ManualVerification);
+        client.add_release_condition(&escrow_id, &arbitrator, &condition);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        client.release_funds(&escrow_id, &arbitrator);
+    }
+
+    #[test]
+    fn test_dispute_release_approved() {
+        let env = Env::default();
+        let (
+            client,
+            depositor,
+            beneficiary,
+            arbitrator,
+            deposit_account,
+            asset,
+            token_client,
+            contract_id,
+            escrow_id,
+        ) = test_setup::setup_contract(&env);
+        let admin = client.get_admin();
+        let manager = register_approval_manager(&env, &contract_id);
+        client.set_approval_manager(&admin, &Some(manager.address.clone()));
+
+        token_client.approve(
+            &deposit_account,
+            &contract_id,
+            &1000,
+            &(env.ledger().sequence() + 1000),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &beneficiary, &String::from_str(&env, "Dispute"));
+
+        let outcome = DisputeOutcome::PartialRelease(6000);
+        approve_action(
+            &env,
+            &manager,
+            &contract_id,
+            &client.get_outcome_payload(&escrow_id, &outcome),
+        );
+        client.resolve_dispute(&escrow_id, &arbitrator, &outcome);
+
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Resolved);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 600);
+    }
+
+    #[test]
+    fn test_dispute_release_without_approval() {
+        let env = Env::default();
+        let (
+            client,
+            depositor,
+            beneficiary,
+            arbitrator,
+            deposit_account,
+            _,
+            token_client,
+            contract_id,
+            escrow_id,
+        ) = test_setup::setup_contract(&env);
+        let admin = client.get_admin();
+        let manager = register_approval_manager(&env, &contract_id);
+        client.set_approval_manager(&admin, &Some(manager.address));
+
+        token_client.approve(
+            &deposit_account,
+            &contract_id,
+            &1000,
+            &(env.ledger().sequence() + 1000),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &beneficiary, &String::from_str(&env, "Dispute"));
+        let result = client.try_resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
+        assert_eq!(result, Err(Ok(EscrowError::ApprovalRequired)));
+
+        // A full refund pays the beneficiary nothing and needs no approval
+        client.resolve_dispute(&escrow_id, &arbitrator, &DisputeOutcome::RefundToDepositor);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Resolved);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_set_approval_manager_unauthorized() {
+        let env = Env::default();
+        let (client, _, _, arbitrator, _, _, _, _, _) = test_setup::setup_contract(&env);
+        client.set_approval_manager(&arbitrator, &Some(Address::generate(&env)));
     }
 }
 
@@ -297,16 +464,25 @@ mod test_refund {
     #[test]
     fn test_refund_deposit_success() {
         let env = Env::default();
-        let (client, depositor, _, arbitrator, deposit_account, _, token_client, contract_id) =
-            test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
+        let (
+            client,
+            depositor,
+            _,
+            arbitrator,
+            deposit_account,
+            _,
+            token_client,
+            contract_id,
+            escrow_id,
+        ) = test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
         env.ledger().with_mut(|li| {
             li.timestamp = 17280 * 7 * 5 + 1;
         });
         let live_until_ledger = env.ledger().sequence() + 1000; // Valid for 1000 ledgers
         token_client.approve(&deposit_account, &contract_id, &1000000, &live_until_ledger);
-        client.refund_deposit(&arbitrator);
-        assert_eq!(client.get_escrow_status(), EscrowStatus::Refunded);
+        client.refund_deposit(&escrow_id, &arbitrator);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Refunded);
         assert_eq!(token_client.balance(&depositor), 1000000);
         assert_eq!(token_client.balance(&deposit_account), 0);
     }
@@ -315,33 +491,34 @@ mod test_refund {
     #[should_panic(expected = "Error(Contract, #1)")]
     fn test_refund_deposit_unauthorized() {
         let env = Env::default();
-        let (client, depositor, _, _, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
+        let (client, depositor, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
         env.ledger().with_mut(|li| {
             li.timestamp = 17280 * 7 * 5 + 1;
         });
         let unauthorized = Address::generate(&env);
-        client.refund_deposit(&unauthorized);
+        client.refund_deposit(&escrow_id, &unauthorized);
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #4)")]
     fn test_refund_deposit_not_funded() {
         let env = Env::default();
-        let (client, _, _, arbitrator, _, _, _, _) = test_setup::setup_contract(&env);
+        let (client, _, _, arbitrator, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
         env.ledger().with_mut(|li| {
             li.timestamp = 17280 * 7 * 5 + 1;
         });
-        client.refund_deposit(&arbitrator);
+        client.refund_deposit(&escrow_id, &arbitrator);
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #9)")]
     fn test_refund_deposit_timeout_not_reached() {
         let env = Env::default();
-        let (client, depositor, _, arbitrator, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
-        client.refund_deposit(&arbitrator);
+        let (client, depositor, _, arbitrator, _, _, _, _, escrow_id) =
+            test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.refund_deposit(&escrow_id, &arbitrator);
     }
 }
 
@@ -351,38 +528,49 @@ mod test_dispute {
     #[test]
     fn test_initiate_dispute_success() {
         let env = Env::default();
-        let (client, depositor, _, _, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
-        client.initiate_dispute(&depositor, &String::from_str(&env, "Dispute"));
-        assert_eq!(client.get_escrow_status(), EscrowStatus::InDispute);
+        let (client, depositor, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::InDispute
+        );
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #1)")]
     fn test_initiate_dispute_unauthorized() {
         let env = Env::default();
-        let (client, depositor, _, _, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
+        let (client, depositor, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
         let unauthorized = Address::generate(&env);
-        client.initiate_dispute(&unauthorized, &String::from_str(&env, "Dispute"));
+        client.initiate_dispute(
+            &escrow_id,
+            &unauthorized,
+            &String::from_str(&env, "Dispute"),
+        );
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #3)")]
     fn test_initiate_dispute_wrong_status() {
         let env = Env::default();
-        let (client, depositor, _, _, _, _, _, _) = test_setup::setup_contract(&env);
-        client.initiate_dispute(&depositor, &String::from_str(&env, "Dispute"));
+        let (client, depositor, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #3)")]
     fn test_initiate_dispute_already_in_progress() {
         let env = Env::default();
-        let (client, depositor, _, _, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
-        client.initiate_dispute(&depositor, &String::from_str(&env, "Dispute"));
-        client.initiate_dispute(&depositor, &String::from_str(&env, "Another dispute"));
+        let (client, depositor, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        client.initiate_dispute(
+            &escrow_id,
+            &depositor,
+            &String::from_str(&env, "Another dispute"),
+        );
     }
 
     #[test]
@@ -397,13 +585,18 @@ mod test_dispute {
             _,
             token_client,
             contract_id,
+            escrow_id,
         ) = test_setup::setup_contract(&env);
         let live_until_ledger = env.ledger().sequence() + 1000;
         token_client.approve(&deposit_account, &contract_id, &1000, &live_until_ledger);
-        client.deposit_funds(&depositor, &1000);
-        client.initiate_dispute(&depositor, &String::from_str(&env, "Dispute"));
-        client.resolve_dispute(&arbitrator, &DisputeOutcome::ReleaseToBeneficiary);
-        assert_eq!(client.get_escrow_status(), EscrowStatus::Resolved);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Resolved);
         assert_eq!(token_client.balance(&beneficiary), 1000);
         assert_eq!(token_client.balance(&deposit_account), 0);
     }
@@ -411,14 +604,23 @@ mod test_dispute {
     #[test]
     fn test_resolve_dispute_refund_to_depositor() {
         let env = Env::default();
-        let (client, depositor, _, arbitrator, deposit_account, _, token_client, contract_id) =
-            test_setup::setup_contract(&env);
+        let (
+            client,
+            depositor,
+            _,
+            arbitrator,
+            deposit_account,
+            _,
+            token_client,
+            contract_id,
+            escrow_id,
+        ) = test_setup::setup_contract(&env);
         let live_until_ledger = env.ledger().sequence() + 1000;
         token_client.approve(&deposit_account, &contract_id, &1000, &live_until_ledger);
-        client.deposit_funds(&depositor, &1000);
-        client.initiate_dispute(&depositor, &String::from_str(&env, "Dispute"));
-        client.resolve_dispute(&arbitrator, &DisputeOutcome::RefundToDepositor);
-        assert_eq!(client.get_escrow_status(), EscrowStatus::Resolved);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        client.resolve_dispute(&escrow_id, &arbitrator, &DisputeOutcome::RefundToDepositor);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Resolved);
         assert_eq!(token_client.balance(&depositor), 1000000);
         assert_eq!(token_client.balance(&deposit_account), 0);
     }
@@ -435,13 +637,18 @@ mod test_dispute {
             _,
             token_client,
             contract_id,
+            escrow_id,
         ) = test_setup::setup_contract(&env);
         let live_until_ledger = env.ledger().sequence() + 1000;
         token_client.approve(&deposit_account, &contract_id, &1000, &live_until_ledger);
-        client.deposit_funds(&depositor, &1000);
-        client.initiate_dispute(&depositor, &String::from_str(&env, "Dispute"));
-        client.resolve_dispute(&arbitrator, &DisputeOutcome::PartialRelease(5000));
-        assert_eq!(client.get_escrow_status(), EscrowStatus::Resolved);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::PartialRelease(5000),
+        );
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Resolved);
         assert_eq!(token_client.balance(&beneficiary), 500);
         assert_eq!(token_client.balance(&depositor), 999500);
         assert_eq!(token_client.balance(&deposit_account), 0);
@@ -451,29 +658,2754 @@ mod test_dispute {
     #[should_panic(expected = "Error(Contract, #1)")]
     fn test_resolve_dispute_unauthorized() {
         let env = Env::default();
-        let (client, depositor, _, _, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
-        client.initiate_dispute(&depositor, &String::from_str(&env, "Dispute"));
+        let (client, depositor, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
         let unauthorized = Address::generate(&env);
-        client.resolve_dispute(&unauthorized, &DisputeOutcome::ReleaseToBeneficiary);
+        client.resolve_dispute(
+            &escrow_id,
+            &unauthorized,
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #10)")]
     fn test_resolve_dispute_no_dispute() {
         let env = Env::default();
-        let (client, depositor, _, arbitrator, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
-        client.resolve_dispute(&arbitrator, &DisputeOutcome::ReleaseToBeneficiary);
+        let (client, depositor, _, arbitrator, _, _, _, _, escrow_id) =
+            test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
     }
 
     #[test]
     #[should_panic(expected = "Error(Contract, #2)")]
     fn test_resolve_dispute_invalid_basis_points() {
         let env = Env::default();
-        let (client, depositor, _, arbitrator, _, _, _, _) = test_setup::setup_contract(&env);
-        client.deposit_funds(&depositor, &1000);
-        client.initiate_dispute(&depositor, &String::from_str(&env, "Dispute"));
-        client.resolve_dispute(&arbitrator, &DisputeOutcome::PartialRelease(10001));
+        let (client, depositor, _, arbitrator, _, _, _, _, escrow_id) =
+            test_setup::setup_contract(&env);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::PartialRelease(10001),
+        );
+    }
+}
+
+mod test_registry {
+    use super::*;
+
+    #[test]
+    fn test_escrows_are_independent() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, deposit_account, asset, _, _, escrow_id) =
+            test_setup::setup_contract(&env);
+        let other_id = client.create_escrow(
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &Some(deposit_account.clone()),
+            &asset,
+            &2500,
+            &(17280 * 7),
+        );
+
+        client.deposit_funds(&other_id, &depositor, &2500);
+
+        assert_eq!(other_id, escrow_id + 1);
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::Initialized
+        );
+        assert_eq!(client.get_escrow_status(&other_id), EscrowStatus::Funded);
+        assert_eq!(client.get_escrow(&other_id).amount, 2500);
+        assert_eq!(check_balance(&env, &asset.token, &deposit_account), 2500);
+    }
+
+    #[test]
+    fn test_list_escrows_by_party() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, deposit_account, asset, _, _, escrow_id) =
+            test_setup::setup_contract(&env);
+        let other_beneficiary = Address::generate(&env);
+        let other_id = client.create_escrow(
+            &depositor,
+            &other_beneficiary,
+            &arbitrator,
+            &Some(deposit_account.clone()),
+            &asset,
+            &500,
+            &(17280 * 7),
+        );
+
+        assert_eq!(
+            client.get_depositor_escrows(&depositor),
+            vec![&env, escrow_id, other_id]
+        );
+        assert_eq!(
+            client.get_beneficiary_escrows(&beneficiary),
+            vec![&env, escrow_id]
+        );
+        assert_eq!(
+            client.get_beneficiary_escrows(&other_beneficiary),
+            vec![&env, other_id]
+        );
+        assert_eq!(
+            client.get_arbitrator_escrows(&arbitrator),
+            vec![&env, escrow_id, other_id]
+        );
+        assert!(client.get_depositor_escrows(&beneficiary).is_empty());
+    }
+
+    #[test]
+    fn test_conditions_are_kept_per_escrow() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, deposit_account, asset, _, _, escrow_id) =
+            test_setup::setup_contract(&env);
+        let other_id = client.create_escrow(
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &Some(deposit_account.clone()),
+            &asset,
+            &1000,
+            &(17280 * 7),
+        );
+
+        let condition = create_condition(&env, "Test condition", ConditionType::ManualVerification);
+        client.add_release_condition(&escrow_id, &arbitrator, &condition);
+
+        assert_eq!(client.get_release_conditions(&escrow_id).len(), 1);
+        assert!(client.get_release_conditions(&other_id).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #13)")]
+    fn test_unknown_escrow() {
+        let env = Env::default();
+        let (client, depositor, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.deposit_funds(&(escrow_id + 1), &depositor, &1000);
+    }
+}
+
+mod test_custody {
+    use super::*;
+
+    // An escrow of 1000 held by the contract itself, with one condition
+    fn setup_custody_escrow(
+        env: &Env,
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+    ) -> u64 {
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        let condition = create_condition(env, "Test condition", ConditionType::ManualVerification);
+        client.add_release_condition(&escrow_id, arbitrator, &condition);
+        client.deposit_funds(&escrow_id, depositor, &1000);
+        escrow_id
+    }
+
+    #[test]
+    fn test_deposit_held_by_contract() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            setup_custody_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        assert_eq!(client.get_held_balance(&escrow_id), 1000);
+        assert_eq!(check_balance(&env, &asset.token, &contract_id), 1000);
+    }
+
+    #[test]
+    fn test_release_without_allowance() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            setup_custody_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        client.release_funds(&escrow_id, &arbitrator);
+
+        assert_eq!(client.get_held_balance(&escrow_id), 0);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+        assert_eq!(check_balance(&env, &asset.token, &contract_id), 0);
+    }
+
+    #[test]
+    fn test_refund_without_allowance() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            setup_custody_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 17280 * 7 * 5 + 1;
+        });
+        client.refund_deposit(&escrow_id, &arbitrator);
+
+        assert_eq!(client.get_held_balance(&escrow_id), 0);
+        assert_eq!(check_balance(&env, &asset.token, &depositor), 1000000);
+    }
+
+    #[test]
+    fn test_partial_release_keeps_balances_apart() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let first =
+            setup_custody_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        let second =
+            setup_custody_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.initiate_dispute(&first, &depositor, &String::from_str(&env, "Dispute"));
+        client.resolve_dispute(&first, &arbitrator, &DisputeOutcome::PartialRelease(2500));
+
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 250);
+        assert_eq!(client.get_held_balance(&first), 0);
+        assert_eq!(client.get_held_balance(&second), 1000);
+        assert_eq!(check_balance(&env, &asset.token, &contract_id), 1000);
+    }
+}
+
+mod test_milestones {
+    use super::*;
+    use crate::testutils::create_milestone;
+
+    // A funded 1000 escrow in custody paying 300, then 20%, then the rest
+    fn setup_milestone_escrow(
+        env: &Env,
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+    ) -> u64 {
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.add_release_condition(
+            &escrow_id,
+            arbitrator,
+            &create_milestone(env, "Design", ConditionPayout::Amount(300)),
+        );
+        client.add_release_condition(
+            &escrow_id,
+            arbitrator,
+            &create_milestone(env, "Build", ConditionPayout::Share(2000)),
+        );
+        client.add_release_condition(
+            &escrow_id,
+            arbitrator,
+            &create_condition(env, "Handover", ConditionType::ManualVerification),
+        );
+        client.deposit_funds(&escrow_id, depositor, &1000);
+        escrow_id
+    }
+
+    #[test]
+    fn test_milestones_release_in_stages() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            setup_milestone_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 300);
+        client.verify_condition(&escrow_id, &arbitrator, &1);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 500);
+        assert_eq!(client.get_remaining_balance(&escrow_id), 500);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
+
+        client.verify_condition(&escrow_id, &arbitrator, &2);
+        client.release_funds(&escrow_id, &arbitrator);
+
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+        assert_eq!(client.get_remaining_balance(&escrow_id), 0);
+        assert_eq!(client.get_held_balance(&escrow_id), 0);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Released);
+    }
+
+    #[test]
+    fn test_last_milestone_completes_escrow() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id = client.create_escrow(
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &None,
+            &asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_milestone(&env, "Half", ConditionPayout::Share(5000)),
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_milestone(&env, "Rest", ConditionPayout::Amount(500)),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        client.verify_condition(&escrow_id, &arbitrator, &1);
+
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Released);
+    }
+
+    #[test]
+    fn test_refund_returns_unreleased_part() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            setup_milestone_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 17280 * 7 * 5 + 1;
+        });
+        client.refund_deposit(&escrow_id, &arbitrator);
+
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 300);
+        assert_eq!(check_balance(&env, &asset.token, &depositor), 999700);
+        assert_eq!(client.get_held_balance(&escrow_id), 0);
+    }
+
+    #[test]
+    fn test_dispute_splits_unreleased_part() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            setup_milestone_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::PartialRelease(5000),
+        );
+
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 650);
+        assert_eq!(check_balance(&env, &asset.token, &depositor), 999350);
+        assert_eq!(client.get_held_balance(&escrow_id), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #2)")]
+    fn test_milestones_exceeding_amount() {
+        let env = Env::default();
+        let (client, _, _, arbitrator, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_milestone(&env, "Most", ConditionPayout::Share(8000)),
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_milestone(&env, "Too much", ConditionPayout::Amount(201)),
+        );
+    }
+
+    #[test]
+    fn test_milestone_needs_approval() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            setup_milestone_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        let manager = register_approval_manager(&env, &contract_id);
+        client.set_approval_manager(&client.get_admin(), &Some(manager.address.clone()));
+
+        let result = client.try_verify_condition(&escrow_id, &arbitrator, &0);
+        assert_eq!(result, Err(Ok(EscrowError::ApprovalRequired)));
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 0);
+
+        approve_action(
+            &env,
+            &manager,
+            &contract_id,
+            &client.get_milestone_payload(&escrow_id, &0),
+        );
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 300);
+
+        // An approval for the final release does not cover a milestone
+        approve_action(
+            &env,
+            &manager,
+            &contract_id,
+            &client.get_release_payload(&escrow_id),
+        );
+        let result = client.try_verify_condition(&escrow_id, &arbitrator, &1);
+        assert_eq!(result, Err(Ok(EscrowError::ApprovalRequired)));
+    }
+}
+
+mod test_time_conditions {
+    use super::*;
+    use crate::testutils::{create_milestone, create_time_condition};
+
+    // A funded 1000 escrow in custody with no conditions yet
+    fn setup_custody_escrow(
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+    ) -> u64 {
+        client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        )
+    }
+
+    #[test]
+    fn test_unlock_time_fulfils_condition() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            setup_custody_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+        let unlock_time = env.ledger().timestamp() + 3600;
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_time_condition(&env, ConditionTrigger::UnlockTime(unlock_time)),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+
+        assert_eq!(client.evaluate_conditions(&escrow_id), 0);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
+
+        env.ledger().with_mut(|li| li.timestamp = unlock_time);
+        assert_eq!(client.evaluate_conditions(&escrow_id), 1);
+        assert!(
+            client
+                .get_release_conditions(&escrow_id)
+                .get(0)
+                .unwrap()
+                .is_fulfilled
+        );
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::ConditionsMet
+        );
+
+        client.release_funds(&escrow_id, &arbitrator);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+    }
+
+    #[test]
+    fn test_unlock_ledger_fulfils_condition() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            setup_custody_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+        let unlock_ledger = env.ledger().sequence() + 100;
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_time_condition(&env, ConditionTrigger::UnlockLedger(unlock_ledger)),
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_condition(&env, "Delivery", ConditionType::ManualVerification),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+
+        env.ledger()
+            .with_mut(|li| li.sequence_number = unlock_ledger);
+        assert_eq!(client.evaluate_conditions(&escrow_id), 1);
+        // Manual conditions are left for the arbitrator
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
+        assert_eq!(client.evaluate_conditions(&escrow_id), 0);
+
+        client.verify_condition(&escrow_id, &arbitrator, &1);
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::ConditionsMet
+        );
+    }
+
+    #[test]
+    fn test_time_milestone_paid_on_evaluate() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            setup_custody_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+        let unlock_time = env.ledger().timestamp() + 3600;
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &Condition {
+                trigger: ConditionTrigger::UnlockTime(unlock_time),
+                condition_type: ConditionType::TimeBased,
+                ..create_milestone(&env, "First tranche", ConditionPayout::Share(4000))
+            },
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_condition(&env, "Delivery", ConditionType::ManualVerification),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+
+        env.ledger().with_mut(|li| li.timestamp = unlock_time + 1);
+        client.evaluate_conditions(&escrow_id);
+
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 400);
+        assert_eq!(client.get_remaining_balance(&escrow_id), 600);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #14)")]
+    fn test_verify_locked_condition() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            setup_custody_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+        let unlock_time = env.ledger().timestamp() + 3600;
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_time_condition(&env, ConditionTrigger::UnlockTime(unlock_time)),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_time_condition_without_unlock() {
+        let env = Env::default();
+        let (client, _, _, arbitrator, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_condition(&env, "Unlock", ConditionType::TimeBased),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_evaluate_unfunded_escrow() {
+        let env = Env::default();
+        let (client, _, _, _, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.evaluate_conditions(&escrow_id);
+    }
+}
+
+mod test_oracle_conditions {
+    use super::*;
+    use crate::testutils::{create_oracle_condition, MockOracle, MockOracleClient};
+    use soroban_sdk::Bytes;
+
+    // A funded 1000 escrow in custody with one condition attested by a mock oracle
+    fn setup_oracle_escrow<'a>(
+        env: &Env,
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+    ) -> (u64, MockOracleClient<'a>) {
+        let oracle = MockOracleClient::new(env, &env.register(MockOracle, ()));
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.add_release_condition(
+            &escrow_id,
+            arbitrator,
+            &create_oracle_condition(env, &oracle.address, "shipment-42"),
+        );
+        client.deposit_funds(&escrow_id, depositor, &1000);
+        (escrow_id, oracle)
+    }
+
+    #[test]
+    fn test_oracle_attestation_fulfils_condition() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, oracle) =
+            setup_oracle_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        assert_eq!(client.evaluate_conditions(&escrow_id), 0);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
+
+        oracle.set(&Bytes::from_slice(&env, b"shipment-42"), &true);
+        assert_eq!(client.evaluate_conditions(&escrow_id), 1);
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::ConditionsMet
+        );
+
+        client.release_funds(&escrow_id, &arbitrator);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+    }
+
+    #[test]
+    fn test_oracle_only_attests_its_key() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, oracle) =
+            setup_oracle_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        oracle.set(&Bytes::from_slice(&env, b"shipment-43"), &true);
+        oracle.set(&Bytes::from_slice(&env, b"shipment-42"), &false);
+
+        assert_eq!(client.evaluate_conditions(&escrow_id), 0);
+        assert!(
+            !client
+                .get_release_conditions(&escrow_id)
+                .get(0)
+                .unwrap()
+                .is_fulfilled
+        );
+    }
+
+    #[test]
+    fn test_failing_oracle_does_not_attest() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        // A contract without `attest` stands in for a broken oracle
+        let broken_oracle = env.register(approval_manager::ApprovalManager, ());
+        let escrow_id = client.create_escrow(
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &None,
+            &asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_oracle_condition(&env, &broken_oracle, "shipment-42"),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+
+        assert_eq!(client.evaluate_conditions(&escrow_id), 0);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_oracle_condition_without_oracle() {
+        let env = Env::default();
+        let (client, _, _, arbitrator, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_condition(&env, "Oracle attestation", ConditionType::ExternalOracle),
+        );
+    }
+}
+
+mod test_multisig_conditions {
+    use super::*;
+    use crate::testutils::create_multisig_condition;
+
+    // A funded 1000 escrow in custody needing 2 of 3 signers
+    fn setup_multisig_escrow(
+        env: &Env,
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+    ) -> (u64, Vec<Address>) {
+        let signers = vec![
+            env,
+            Address::generate(env),
+            Address::generate(env),
+            Address::generate(env),
+        ];
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.add_release_condition(
+            &escrow_id,
+            arbitrator,
+            &create_multisig_condition(env, &signers, 2),
+        );
+        client.deposit_funds(&escrow_id, depositor, &1000);
+        (escrow_id, signers)
+    }
+
+    #[test]
+    fn test_threshold_fulfils_condition() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, signers) =
+            setup_multisig_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.approve_condition(&escrow_id, &signers.get(0).unwrap(), &0);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
+        assert_eq!(client.get_condition_approvals(&escrow_id, &0).len(), 1);
+
+        client.approve_condition(&escrow_id, &signers.get(2).unwrap(), &0);
+        assert!(
+            client
+                .get_release_conditions(&escrow_id)
+                .get(0)
+                .unwrap()
+                .is_fulfilled
+        );
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::ConditionsMet
+        );
+    }
+
+    #[test]
+    fn test_withdrawn_approval_does_not_count() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, signers) =
+            setup_multisig_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.approve_condition(&escrow_id, &signers.get(0).unwrap(), &0);
+        client.withdraw_approval(&escrow_id, &signers.get(0).unwrap(), &0);
+        assert_eq!(client.get_condition_approvals(&escrow_id, &0).len(), 0);
+
+        client.approve_condition(&escrow_id, &signers.get(1).unwrap(), &0);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
+
+        client.approve_condition(&escrow_id, &signers.get(0).unwrap(), &0);
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::ConditionsMet
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_approve_by_non_signer() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, _) =
+            setup_multisig_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.approve_condition(&escrow_id, &arbitrator, &0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #15)")]
+    fn test_approve_twice() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, signers) =
+            setup_multisig_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.approve_condition(&escrow_id, &signers.get(0).unwrap(), &0);
+        client.approve_condition(&escrow_id, &signers.get(0).unwrap(), &0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #16)")]
+    fn test_withdraw_without_approval() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, signers) =
+            setup_multisig_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.withdraw_approval(&escrow_id, &signers.get(0).unwrap(), &0);
+    }
+
+    #[test]
+    fn test_withdraw_after_resolution() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, signers) =
+            setup_multisig_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        client.approve_condition(&escrow_id, &signers.get(0).unwrap(), &0);
+
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        client.resolve_dispute(&escrow_id, &arbitrator, &DisputeOutcome::RefundToDepositor);
+
+        let result = client.try_withdraw_approval(&escrow_id, &signers.get(0).unwrap(), &0);
+        assert_eq!(result, Err(Ok(EscrowError::InvalidStatus)));
+        assert_eq!(client.get_condition_approvals(&escrow_id, &0).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #8)")]
+    fn test_withdraw_after_fulfilled() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, signers) =
+            setup_multisig_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.approve_condition(&escrow_id, &signers.get(0).unwrap(), &0);
+        client.approve_condition(&escrow_id, &signers.get(1).unwrap(), &0);
+        client.withdraw_approval(&escrow_id, &signers.get(0).unwrap(), &0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_unreachable_threshold() {
+        let env = Env::default();
+        let (client, _, _, arbitrator, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        let signers = vec![&env, Address::generate(&env)];
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_multisig_condition(&env, &signers, 2),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_duplicate_signers() {
+        let env = Env::default();
+        let (client, _, _, arbitrator, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        let signer = Address::generate(&env);
+        let signers = vec![&env, signer.clone(), signer, Address::generate(&env)];
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_multisig_condition(&env, &signers, 3),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #14)")]
+    fn test_arbitrator_cannot_verify() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, _) =
+            setup_multisig_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+    }
+}
+
+mod test_arbitration_panel {
+    use super::*;
+
+    // A funded 1000 escrow in custody whose dispute is decided by a panel
+    #[allow(clippy::too_many_arguments)]
+    fn setup_panel_dispute(
+        env: &Env,
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+        size: u32,
+        rule: DecisionRule,
+    ) -> (u64, Vec<Address>) {
+        let mut members = Vec::new(env);
+        for _ in 0..size {
+            members.push_back(Address::generate(env));
+        }
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.set_arbitration_panel(
+            &escrow_id,
+            arbitrator,
+            &ArbitrationPanel {
+                members: members.clone(),
+                rule,
+                voting_period: 3600,
+                fallback: DisputeOutcome::RefundToDepositor,
+            },
+        );
+        client.deposit_funds(&escrow_id, depositor, &1000);
+        client.initiate_dispute(&escrow_id, depositor, &String::from_str(env, "Dispute"));
+        (escrow_id, members)
+    }
+
+    #[test]
+    fn test_majority_carries() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, members) = setup_panel_dispute(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            3,
+            DecisionRule::Majority,
+        );
+
+        let release = DisputeOutcome::ReleaseToBeneficiary;
+        client.vote_on_dispute(&escrow_id, &members.get(0).unwrap(), &release);
+        client.vote_on_dispute(&escrow_id, &members.get(1).unwrap(), &release);
+        client.vote_on_dispute(
+            &escrow_id,
+            &members.get(2).unwrap(),
+            &DisputeOutcome::RefundToDepositor,
+        );
+
+        // Every member has voted, so the window does not need to close
+        assert_eq!(client.tally_votes(&escrow_id), release);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Resolved);
+    }
+
+    #[test]
+    fn test_members_vote_their_own_split() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, members) = setup_panel_dispute(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            3,
+            DecisionRule::Majority,
+        );
+
+        let split = DisputeOutcome::PartialRelease(3000);
+        client.vote_on_dispute(&escrow_id, &members.get(0).unwrap(), &split);
+        client.vote_on_dispute(
+            &escrow_id,
+            &members.get(1).unwrap(),
+            &DisputeOutcome::PartialRelease(5000),
+        );
+        // A member may change their vote while the window is open
+        client.vote_on_dispute(&escrow_id, &members.get(1).unwrap(), &split);
+
+        env.ledger().with_mut(|li| li.timestamp += 3600);
+        assert_eq!(client.tally_votes(&escrow_id), split);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 300);
+        assert_eq!(check_balance(&env, &asset.token, &depositor), 999700);
+    }
+
+    #[test]
+    fn test_short_supermajority_falls_back() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, members) = setup_panel_dispute(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            5,
+            DecisionRule::Supermajority,
+        );
+
+        for index in 0..3 {
+            client.vote_on_dispute(
+                &escrow_id,
+                &members.get(index).unwrap(),
+                &DisputeOutcome::ReleaseToBeneficiary,
+            );
+        }
+
+        env.ledger().with_mut(|li| li.timestamp += 3600);
+        assert_eq!(
+            client.tally_votes(&escrow_id),
+            DisputeOutcome::RefundToDepositor
+        );
+        assert_eq!(check_balance(&env, &asset.token, &depositor), 1000000);
+    }
+
+    #[test]
+    fn test_tie_falls_back() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, members) = setup_panel_dispute(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            4,
+            DecisionRule::Majority,
+        );
+
+        for (index, outcome) in [
+            DisputeOutcome::ReleaseToBeneficiary,
+            DisputeOutcome::ReleaseToBeneficiary,
+            DisputeOutcome::PartialRelease(5000),
+            DisputeOutcome::PartialRelease(5000),
+        ]
+        .iter()
+        .enumerate()
+        {
+            client.vote_on_dispute(&escrow_id, &members.get(index as u32).unwrap(), outcome);
+        }
+
+        assert_eq!(
+            client.tally_votes(&escrow_id),
+            DisputeOutcome::RefundToDepositor
+        );
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #18)")]
+    fn test_tally_while_voting_open() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, members) = setup_panel_dispute(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            3,
+            DecisionRule::Majority,
+        );
+
+        client.vote_on_dispute(
+            &escrow_id,
+            &members.get(0).unwrap(),
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
+        client.tally_votes(&escrow_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #17)")]
+    fn test_vote_after_window() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, members) = setup_panel_dispute(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            3,
+            DecisionRule::Majority,
+        );
+
+        env.ledger().with_mut(|li| li.timestamp += 3600);
+        client.vote_on_dispute(
+            &escrow_id,
+            &members.get(0).unwrap(),
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_vote_by_non_member() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, _) = setup_panel_dispute(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            3,
+            DecisionRule::Majority,
+        );
+
+        client.vote_on_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_arbitrator_cannot_overrule_panel() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, _) = setup_panel_dispute(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            3,
+            DecisionRule::Majority,
+        );
+
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #19)")]
+    fn test_empty_panel() {
+        let env = Env::default();
+        let (client, _, _, arbitrator, _, _, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.set_arbitration_panel(
+            &escrow_id,
+            &arbitrator,
+            &ArbitrationPanel {
+                members: Vec::new(&env),
+                rule: DecisionRule::Majority,
+                voting_period: 3600,
+                fallback: DisputeOutcome::RefundToDepositor,
+            },
+        );
+    }
+}
+
+mod test_dispute_appeals {
+    use super::*;
+    use soroban_sdk::BytesN;
+
+    // A funded 1000 escrow in custody under a dispute with an hour of
+    // evidence, a two hour appeal window and a three member appeal panel
+    fn setup_appealable_dispute(
+        env: &Env,
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+    ) -> (u64, Vec<Address>) {
+        let appeal_panel = vec![
+            env,
+            Address::generate(env),
+            Address::generate(env),
+            Address::generate(env),
+        ];
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.set_dispute_terms(
+            &escrow_id,
+            arbitrator,
+            &DisputeTerms {
+                evidence_period: 3600,
+                appeal_window: 7200,
+                appeal_panel: ArbitrationPanel {
+                    members: appeal_panel.clone(),
+                    rule: DecisionRule::Majority,
+                    voting_period: 3600,
+                    fallback: DisputeOutcome::RefundToDepositor,
+                },
+            },
+        );
+        client.deposit_funds(&escrow_id, depositor, &1000);
+        client.initiate_dispute(&escrow_id, beneficiary, &String::from_str(env, "Unpaid"));
+        (escrow_id, appeal_panel)
+    }
+
+    #[test]
+    fn test_submit_evidence() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, _) =
+            setup_appealable_dispute(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        env.ledger().with_mut(|li| li.timestamp += 600);
+        let digest = BytesN::from_array(&env, &[7; 32]);
+        client.submit_evidence(
+            &escrow_id,
+            &beneficiary,
+            &digest,
+            &String::from_str(&env, "ipfs://delivery-note"),
+        );
+
+        let evidence = client.get_evidence(&escrow_id);
+        assert_eq!(evidence.len(), 1);
+        let submitted = evidence.get(0).unwrap();
+        assert_eq!(submitted.submitter, beneficiary);
+        assert_eq!(submitted.digest, digest);
+        assert_eq!(submitted.submitted_at, env.ledger().timestamp());
+    }
+
+    #[test]
+    fn test_ruling_waits_out_appeal_window() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, _) =
+            setup_appealable_dispute(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        env.ledger().with_mut(|li| li.timestamp += 3600);
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
+
+        // No funds move until the appeal window closes
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 0);
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::InDispute
+        );
+        assert_eq!(
+            client.try_finalize_dispute(&escrow_id),
+            Err(Ok(EscrowError::AppealOpen))
+        );
+
+        env.ledger().with_mut(|li| li.timestamp += 7200);
+        client.finalize_dispute(&escrow_id);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Resolved);
+    }
+
+    #[test]
+    fn test_appeal_escalates_to_appeal_panel() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, appeal_panel) =
+            setup_appealable_dispute(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        env.ledger().with_mut(|li| li.timestamp += 3600);
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
+        client.appeal_ruling(&escrow_id, &depositor);
+        assert!(client.get_dispute(&escrow_id).appealed);
+
+        // The original arbitrator no longer decides
+        assert_eq!(
+            client.try_resolve_dispute(
+                &escrow_id,
+                &arbitrator,
+                &DisputeOutcome::ReleaseToBeneficiary
+            ),
+            Err(Ok(EscrowError::Unauthorized))
+        );
+
+        let split = DisputeOutcome::PartialRelease(2500);
+        client.vote_on_dispute(&escrow_id, &appeal_panel.get(0).unwrap(), &split);
+        client.vote_on_dispute(&escrow_id, &appeal_panel.get(1).unwrap(), &split);
+        env.ledger().with_mut(|li| li.timestamp += 3600);
+
+        // The appeal panel's decision is final and pays out right away
+        assert_eq!(client.tally_votes(&escrow_id), split);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 250);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Resolved);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #21)")]
+    fn test_ruling_during_evidence_period() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, _) =
+            setup_appealable_dispute(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
+    }
+
+    #[test]
+    fn test_panel_votes_after_evidence_period() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let member = Address::generate(&env);
+        let escrow_id = client.create_escrow(
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &None,
+            &asset,
+            &1000,
+            &(17280 * 7),
+        );
+        let panel = ArbitrationPanel {
+            members: vec![&env, member.clone()],
+            rule: DecisionRule::Majority,
+            voting_period: 3600,
+            fallback: DisputeOutcome::RefundToDepositor,
+        };
+        client.set_arbitration_panel(&escrow_id, &arbitrator, &panel);
+        client.set_dispute_terms(
+            &escrow_id,
+            &arbitrator,
+            &DisputeTerms {
+                evidence_period: 3600,
+                appeal_window: 7200,
+                appeal_panel: panel,
+            },
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &beneficiary, &String::from_str(&env, "Unpaid"));
+
+        let release = DisputeOutcome::ReleaseToBeneficiary;
+        let result = client.try_vote_on_dispute(&escrow_id, &member, &release);
+        assert_eq!(result, Err(Ok(EscrowError::EvidenceOpen)));
+
+        env.ledger().with_mut(|li| li.timestamp += 3600);
+        client.vote_on_dispute(&escrow_id, &member, &release);
+        assert_eq!(client.get_dispute_votes(&escrow_id).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #20)")]
+    fn test_evidence_after_period() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, _) =
+            setup_appealable_dispute(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        env.ledger().with_mut(|li| li.timestamp += 3600);
+        client.submit_evidence(
+            &escrow_id,
+            &depositor,
+            &BytesN::from_array(&env, &[1; 32]),
+            &String::from_str(&env, "ipfs://late"),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_evidence_from_outsider() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, _) =
+            setup_appealable_dispute(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.submit_evidence(
+            &escrow_id,
+            &arbitrator,
+            &BytesN::from_array(&env, &[1; 32]),
+            &String::from_str(&env, "ipfs://outsider"),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #23)")]
+    fn test_appeal_after_window() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, _) =
+            setup_appealable_dispute(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        env.ledger().with_mut(|li| li.timestamp += 3600);
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
+        env.ledger().with_mut(|li| li.timestamp += 7200);
+        client.appeal_ruling(&escrow_id, &depositor);
+    }
+}
+
+mod test_installments {
+    use super::*;
+
+    fn create_custody_escrow(
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+    ) -> u64 {
+        client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        )
+    }
+
+    #[test]
+    fn test_installments_build_up_to_funded() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_custody_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.deposit_funds(&escrow_id, &depositor, &400);
+        assert_eq!(client.get_funding(&escrow_id), (400, 600));
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::Initialized
+        );
+
+        client.deposit_funds(&escrow_id, &depositor, &600);
+        assert_eq!(client.get_funding(&escrow_id), (1000, 0));
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
+        assert_eq!(check_balance(&env, &asset.token, &contract_id), 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #2)")]
+    fn test_installment_overshooting_target() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_custody_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.deposit_funds(&escrow_id, &depositor, &700);
+        client.deposit_funds(&escrow_id, &depositor, &400);
+    }
+
+    #[test]
+    fn test_refund_partly_funded_escrow() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_custody_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        client.deposit_funds(&escrow_id, &depositor, &250);
+        client.deposit_funds(&escrow_id, &depositor, &150);
+
+        let timeout = client.get_escrow(&escrow_id).timeout_time;
+        env.ledger().with_mut(|li| li.timestamp = timeout);
+        client.refund_deposit(&escrow_id, &arbitrator);
+
+        assert_eq!(check_balance(&env, &asset.token, &depositor), 1000000);
+        assert_eq!(client.get_held_balance(&escrow_id), 0);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Refunded);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #4)")]
+    fn test_refund_without_deposits() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_custody_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        let timeout = client.get_escrow(&escrow_id).timeout_time;
+        env.ledger().with_mut(|li| li.timestamp = timeout);
+        client.refund_deposit(&escrow_id, &arbitrator);
+    }
+}
+
+mod test_baskets {
+    use super::*;
+    use crate::testutils::create_milestone;
+
+    // A 1000 escrow in custody with a second asset leg of the given amount
+    fn setup_basket_escrow(
+        env: &Env,
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+        leg_amount: i128,
+    ) -> (u64, Asset) {
+        let (leg_token, leg_admin) = create_token_contract(env, &Address::generate(env));
+        mint_tokens(&leg_admin, depositor, &1000000);
+        let leg_asset = Asset {
+            token: leg_token,
+            symbol: String::from_str(env, "EURC"),
+            decimals: 7,
+        };
+
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.add_basket_leg(&escrow_id, depositor, &leg_asset, &leg_amount);
+        (escrow_id, leg_asset)
+    }
+
+    #[test]
+    fn test_legs_deposited_in_step() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, leg_asset) = setup_basket_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            900,
+        );
+
+        client.deposit_funds(&escrow_id, &depositor, &400);
+        assert_eq!(check_balance(&env, &leg_asset.token, &contract_id), 360);
+
+        client.deposit_funds(&escrow_id, &depositor, &600);
+        assert_eq!(check_balance(&env, &leg_asset.token, &contract_id), 900);
+        assert_eq!(client.get_basket(&escrow_id).get(0).unwrap().funded, 900);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
+    }
+
+    #[test]
+    fn test_release_pays_every_leg() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, leg_asset) = setup_basket_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            900,
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_milestone(&env, "Shipped", ConditionPayout::Share(3000)),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 300);
+        assert_eq!(check_balance(&env, &leg_asset.token, &beneficiary), 270);
+
+        client.release_funds(&escrow_id, &arbitrator);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+        assert_eq!(check_balance(&env, &leg_asset.token, &beneficiary), 900);
+        assert_eq!(check_balance(&env, &leg_asset.token, &contract_id), 0);
+    }
+
+    #[test]
+    fn test_legs_pay_fees_in_own_token() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let fee_collector = Address::generate(&env);
+        client.set_fee_schedule(
+            &client.get_admin(),
+            &Some(FeeSchedule {
+                fee_collector: fee_collector.clone(),
+                platform_fee_bps: 250,
+                arbitrator_fee: 0,
+                dispute_surcharge: 0,
+            }),
+        );
+        let (escrow_id, leg_asset) = setup_basket_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            400,
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_condition(&env, "Delivery", ConditionType::ManualVerification),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        client.release_funds(&escrow_id, &arbitrator);
+
+        assert_eq!(check_balance(&env, &leg_asset.token, &beneficiary), 390);
+        assert_eq!(check_balance(&env, &leg_asset.token, &fee_collector), 10);
+        assert_eq!(client.get_fee_balance(&leg_asset.token), 10);
+        assert_eq!(client.get_fee_balance(&asset.token), 25);
+    }
+
+    #[test]
+    fn test_partial_release_splits_every_leg() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, leg_asset) = setup_basket_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            7,
+        );
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::PartialRelease(3333),
+        );
+
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 333);
+        assert_eq!(check_balance(&env, &leg_asset.token, &beneficiary), 2);
+        // The depositor gets the rest of the leg, rounding included
+        assert_eq!(
+            check_balance(&env, &leg_asset.token, &depositor),
+            1000000 - 2
+        );
+        assert_eq!(check_balance(&env, &leg_asset.token, &contract_id), 0);
+    }
+
+    #[test]
+    fn test_refund_returns_every_leg() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, leg_asset) = setup_basket_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            900,
+        );
+        client.deposit_funds(&escrow_id, &depositor, &500);
+
+        let timeout = client.get_escrow(&escrow_id).timeout_time;
+        env.ledger().with_mut(|li| li.timestamp = timeout);
+        client.refund_deposit(&escrow_id, &arbitrator);
+
+        assert_eq!(check_balance(&env, &asset.token, &depositor), 1000000);
+        assert_eq!(check_balance(&env, &leg_asset.token, &depositor), 1000000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #24)")]
+    fn test_duplicate_leg() {
+        let env = Env::default();
+        let (client, depositor, _, _, _, asset, _, _, escrow_id) = test_setup::setup_contract(&env);
+        client.add_basket_leg(&escrow_id, &depositor, &asset, &500);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_leg_after_first_deposit() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, leg_asset) = setup_basket_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            900,
+        );
+        client.deposit_funds(&escrow_id, &depositor, &100);
+        client.add_basket_leg(
+            &escrow_id,
+            &depositor,
+            &Asset {
+                token: Address::generate(&env),
+                ..leg_asset
+            },
+            &100,
+        );
+    }
+}
+
+mod test_fees {
+    use super::*;
+
+    // Set a 2.5% platform fee, a 50 arbitrator fee and a 20 dispute
+    // surcharge, then create a 1000 escrow in custody under it
+    fn setup_fee_escrow(
+        env: &Env,
+        client: &EscrowContractClient,
+        contract_id: &Address,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+    ) -> (u64, Address) {
+        let fee_collector = Address::generate(env);
+        let admin = env.as_contract(contract_id, || EscrowContract::get_admin(env).unwrap());
+        client.set_fee_schedule(
+            &admin,
+            &Some(FeeSchedule {
+                fee_collector: fee_collector.clone(),
+                platform_fee_bps: 250,
+                arbitrator_fee: 50,
+                dispute_surcharge: 20,
+            }),
+        );
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.deposit_funds(&escrow_id, depositor, &1000);
+        (escrow_id, fee_collector)
+    }
+
+    #[test]
+    fn test_platform_fee_on_release() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, fee_collector) = setup_fee_escrow(
+            &env,
+            &client,
+            &contract_id,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_condition(&env, "Delivery", ConditionType::ManualVerification),
+        );
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        client.release_funds(&escrow_id, &arbitrator);
+
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 975);
+        assert_eq!(check_balance(&env, &asset.token, &fee_collector), 25);
+        assert_eq!(client.get_fee_balance(&asset.token), 25);
+        assert_eq!(client.get_held_balance(&escrow_id), 0);
+    }
+
+    #[test]
+    fn test_schedule_fixed_at_creation() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, escrow_id) =
+            test_setup::setup_contract(&env);
+        let (_, fee_collector) = setup_fee_escrow(
+            &env,
+            &client,
+            &contract_id,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+        );
+
+        // The escrow created before the schedule was set pays no fees
+        assert_eq!(client.get_fee_schedule(&escrow_id), None);
+        client.deposit_funds(&escrow_id, &depositor, &1000);
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        assert_eq!(check_balance(&env, &asset.token, &fee_collector), 0);
+    }
+
+    #[test]
+    fn test_losing_initiator_pays_surcharge() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, fee_collector) = setup_fee_escrow(
+            &env,
+            &client,
+            &contract_id,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+        );
+
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::ReleaseToBeneficiary,
+        );
+
+        // 50 arbitrator fee, the surcharge out of the depositor's 950 and
+        // 2.5% of the 930 paid to the beneficiary
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 907);
+        assert_eq!(check_balance(&env, &asset.token, &fee_collector), 93);
+        assert_eq!(
+            check_balance(&env, &asset.token, &depositor),
+            1000000 - 1000
+        );
+        assert_eq!(client.get_fee_balance(&asset.token), 93);
+    }
+
+    #[test]
+    fn test_losing_respondent_pays_surcharge() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, fee_collector) = setup_fee_escrow(
+            &env,
+            &client,
+            &contract_id,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+        );
+
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        client.resolve_dispute(&escrow_id, &arbitrator, &DisputeOutcome::RefundToDepositor);
+
+        // 50 arbitrator fee and the surcharge the beneficiary's claim on the
+        // remaining 950 is charged
+        assert_eq!(
+            check_balance(&env, &asset.token, &depositor),
+            1000000 - 1000 + 930
+        );
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 0);
+        assert_eq!(check_balance(&env, &asset.token, &fee_collector), 70);
+        assert_eq!(check_balance(&env, &asset.token, &contract_id), 0);
+    }
+
+    #[test]
+    fn test_surcharge_from_losing_share() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let (escrow_id, fee_collector) = setup_fee_escrow(
+            &env,
+            &client,
+            &contract_id,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+        );
+
+        client.initiate_dispute(&escrow_id, &depositor, &String::from_str(&env, "Dispute"));
+        client.resolve_dispute(
+            &escrow_id,
+            &arbitrator,
+            &DisputeOutcome::PartialRelease(2000),
+        );
+
+        // 50 arbitrator fee, then the surcharge out of the beneficiary's 190
+        // and 2.5% of the 170 left to them
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 166);
+        assert_eq!(
+            check_balance(&env, &asset.token, &depositor),
+            1000000 - 1000 + 760
+        );
+        assert_eq!(check_balance(&env, &asset.token, &fee_collector), 74);
+        assert_eq!(check_balance(&env, &asset.token, &contract_id), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #2)")]
+    fn test_platform_fee_over_100_percent() {
+        let env = Env::default();
+        let (client, _, _, _, _, _, _, contract_id, _) = test_setup::setup_contract(&env);
+        let admin = env.as_contract(&contract_id, || EscrowContract::get_admin(&env).unwrap());
+        client.set_fee_schedule(
+            &admin,
+            &Some(FeeSchedule {
+                fee_collector: Address::generate(&env),
+                platform_fee_bps: 10001,
+                arbitrator_fee: 0,
+                dispute_surcharge: 0,
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_set_fee_schedule_unauthorized() {
+        let env = Env::default();
+        let (client, depositor, _, _, _, _, _, _, _) = test_setup::setup_contract(&env);
+        client.set_fee_schedule(&depositor, &None);
+    }
+}
+
+mod test_amendments {
+    use super::*;
+
+    fn create_funded_escrow(
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+    ) -> u64 {
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.deposit_funds(&escrow_id, depositor, &1000);
+        escrow_id
+    }
+
+    #[test]
+    fn test_raise_amount_tops_up() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_funded_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        let version =
+            client.propose_amendment(&escrow_id, &depositor, &AmendmentChange::Amount(1500));
+        assert_eq!(version, 1);
+        client.approve_amendment(&escrow_id, &beneficiary, &version);
+
+        let escrow = client.get_escrow(&escrow_id);
+        assert_eq!(escrow.amount, 1500);
+        assert_eq!(escrow.funded, 1500);
+        assert_eq!(escrow.status, EscrowStatus::Funded);
+        assert_eq!(check_balance(&env, &asset.token, &contract_id), 1500);
+
+        let amendment = client.get_amendments(&escrow_id).get(0).unwrap();
+        assert_eq!(amendment.proposer, depositor);
+        assert_eq!(amendment.status, AmendmentStatus::Approved);
+    }
+
+    #[test]
+    fn test_lower_amount_refunds_difference() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, contract_id, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_funded_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        let version =
+            client.propose_amendment(&escrow_id, &beneficiary, &AmendmentChange::Amount(600));
+        client.approve_amendment(&escrow_id, &depositor, &version);
+
+        assert_eq!(check_balance(&env, &asset.token, &depositor), 1000000 - 600);
+        assert_eq!(check_balance(&env, &asset.token, &contract_id), 600);
+        assert_eq!(client.get_remaining_balance(&escrow_id), 600);
+    }
+
+    #[test]
+    fn test_lower_amount_completes_funding() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id = client.create_escrow(
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &None,
+            &asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.deposit_funds(&escrow_id, &depositor, &400);
+
+        let version =
+            client.propose_amendment(&escrow_id, &depositor, &AmendmentChange::Amount(400));
+        client.approve_amendment(&escrow_id, &beneficiary, &version);
+
+        assert_eq!(client.get_funding(&escrow_id), (400, 0));
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_proposer_cannot_approve() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_funded_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        let timeout_time = client.get_escrow(&escrow_id).timeout_time;
+
+        let version = client.propose_amendment(
+            &escrow_id,
+            &depositor,
+            &AmendmentChange::ExtendTimeout(timeout_time + 1),
+        );
+        client.approve_amendment(&escrow_id, &depositor, &version);
+    }
+
+    #[test]
+    fn test_extend_timeout() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_funded_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        let timeout_time = client.get_escrow(&escrow_id).timeout_time;
+
+        let result = client.try_propose_amendment(
+            &escrow_id,
+            &beneficiary,
+            &AmendmentChange::ExtendTimeout(timeout_time),
+        );
+        assert_eq!(result, Err(Ok(EscrowError::InvalidAmendment)));
+
+        let version = client.propose_amendment(
+            &escrow_id,
+            &beneficiary,
+            &AmendmentChange::ExtendTimeout(timeout_time * 2),
+        );
+        client.approve_amendment(&escrow_id, &depositor, &version);
+
+        // The original timeout no longer allows a refund
+        env.ledger().with_mut(|li| li.timestamp = timeout_time);
+        let result = client.try_refund_deposit(&escrow_id, &arbitrator);
+        assert_eq!(result, Err(Ok(EscrowError::TimeoutNotReached)));
+    }
+
+    #[test]
+    fn test_swap_arbitrator() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_funded_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+        let new_arbitrator = Address::generate(&env);
+
+        let version = client.propose_amendment(
+            &escrow_id,
+            &depositor,
+            &AmendmentChange::Arbitrator(new_arbitrator.clone()),
+        );
+        client.approve_amendment(&escrow_id, &beneficiary, &version);
+
+        assert!(!client
+            .get_arbitrator_escrows(&arbitrator)
+            .contains(escrow_id));
+        assert_eq!(
+            client.get_arbitrator_escrows(&new_arbitrator),
+            vec![&env, escrow_id]
+        );
+
+        // Only the new arbitrator can verify conditions now
+        client.add_release_condition(
+            &escrow_id,
+            &new_arbitrator,
+            &create_condition(&env, "Delivery", ConditionType::ManualVerification),
+        );
+        let result = client.try_verify_condition(&escrow_id, &arbitrator, &0);
+        assert_eq!(result, Err(Ok(EscrowError::Unauthorized)));
+        client.verify_condition(&escrow_id, &new_arbitrator, &0);
+        client.release_funds(&escrow_id, &new_arbitrator);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+    }
+
+    #[test]
+    fn test_remove_last_open_condition() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_funded_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_condition(&env, "Inspection", ConditionType::ManualVerification),
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_condition(&env, "Delivery", ConditionType::ManualVerification),
+        );
+        client.verify_condition(&escrow_id, &arbitrator, &1);
+
+        let result = client.try_propose_amendment(
+            &escrow_id,
+            &depositor,
+            &AmendmentChange::RemoveCondition(1),
+        );
+        assert_eq!(result, Err(Ok(EscrowError::AlreadyFulfilled)));
+
+        let version =
+            client.propose_amendment(&escrow_id, &depositor, &AmendmentChange::RemoveCondition(0));
+        client.approve_amendment(&escrow_id, &beneficiary, &version);
+
+        let conditions = client.get_release_conditions(&escrow_id);
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(
+            conditions.get(0).unwrap().description,
+            String::from_str(&env, "Delivery")
+        );
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::ConditionsMet
+        );
+    }
+
+    #[test]
+    fn test_remove_only_condition() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_funded_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_condition(&env, "Delivery", ConditionType::ManualVerification),
+        );
+
+        let version =
+            client.propose_amendment(&escrow_id, &depositor, &AmendmentChange::RemoveCondition(0));
+        client.approve_amendment(&escrow_id, &beneficiary, &version);
+
+        assert!(client.get_release_conditions(&escrow_id).is_empty());
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::ConditionsMet
+        );
+        client.release_funds(&escrow_id, &arbitrator);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+    }
+
+    #[test]
+    fn test_history_keeps_every_version() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_funded_escrow(&client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        let first = client.propose_amendment(&escrow_id, &depositor, &AmendmentChange::Amount(800));
+        // Only one amendment can be open at a time
+        let result =
+            client.try_propose_amendment(&escrow_id, &beneficiary, &AmendmentChange::Amount(900));
+        assert_eq!(result, Err(Ok(EscrowError::AmendmentPending)));
+
+        client.reject_amendment(&escrow_id, &beneficiary, &first);
+        let result = client.try_approve_amendment(&escrow_id, &beneficiary, &first);
+        assert_eq!(result, Err(Ok(EscrowError::NoAmendment)));
+
+        let second =
+            client.propose_amendment(&escrow_id, &beneficiary, &AmendmentChange::Amount(900));
+        client.approve_amendment(&escrow_id, &depositor, &second);
+
+        let amendments = client.get_amendments(&escrow_id);
+        assert_eq!(amendments.len(), 2);
+        assert_eq!(amendments.get(0).unwrap().status, AmendmentStatus::Rejected);
+        assert_eq!(amendments.get(1).unwrap().version, 2);
+        assert_eq!(amendments.get(1).unwrap().status, AmendmentStatus::Approved);
+        assert_eq!(client.get_escrow(&escrow_id).amount, 900);
+    }
+}
+
+mod test_direct_claims {
+    use super::*;
+
+    const GRACE_PERIOD: u64 = 3 * 24 * 60 * 60;
+
+    // Create a funded escrow in custody with a single manual condition
+    fn create_conditional_escrow(
+        env: &Env,
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+    ) -> u64 {
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.deposit_funds(&escrow_id, depositor, &1000);
+        client.add_release_condition(
+            &escrow_id,
+            arbitrator,
+            &create_condition(env, "Delivery", ConditionType::ManualVerification),
+        );
+        escrow_id
+    }
+
+    #[test]
+    fn test_beneficiary_claims_after_grace_period() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_conditional_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        env.ledger().with_mut(|li| li.timestamp = 1000);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        assert_eq!(client.get_escrow(&escrow_id).conditions_met_at, 1000);
+
+        env.ledger()
+            .with_mut(|li| li.timestamp = 1000 + GRACE_PERIOD);
+        client.release_funds(&escrow_id, &beneficiary);
+
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Released);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #28)")]
+    fn test_claim_during_grace_period() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_conditional_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+
+        env.ledger().with_mut(|li| li.timestamp = GRACE_PERIOD - 1);
+        client.release_funds(&escrow_id, &beneficiary);
+    }
+
+    #[test]
+    fn test_grace_period_fixed_at_creation() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let earlier =
+            create_conditional_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        client.set_claim_grace_period(&client.get_admin(), &3600);
+        let escrow_id =
+            create_conditional_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        assert_eq!(client.get_escrow(&earlier).claim_grace_period, GRACE_PERIOD);
+        assert_eq!(client.get_escrow(&escrow_id).claim_grace_period, 3600);
+
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+        env.ledger().with_mut(|li| li.timestamp = 3599);
+        let result = client.try_release_funds(&escrow_id, &beneficiary);
+        assert_eq!(result, Err(Ok(EscrowError::GracePeriodActive)));
+
+        env.ledger().with_mut(|li| li.timestamp = 3600);
+        client.release_funds(&escrow_id, &beneficiary);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+    }
+
+    #[test]
+    fn test_parties_agree_on_grace_period() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_conditional_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        let version = client.propose_amendment(
+            &escrow_id,
+            &beneficiary,
+            &AmendmentChange::ClaimGracePeriod(GRACE_PERIOD * 2),
+        );
+        client.approve_amendment(&escrow_id, &depositor, &version);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+
+        env.ledger().with_mut(|li| li.timestamp = GRACE_PERIOD);
+        let result = client.try_release_funds(&escrow_id, &beneficiary);
+        assert_eq!(result, Err(Ok(EscrowError::GracePeriodActive)));
+
+        env.ledger().with_mut(|li| li.timestamp = GRACE_PERIOD * 2);
+        client.release_funds(&escrow_id, &beneficiary);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_set_grace_period_unauthorized() {
+        let env = Env::default();
+        let (client, depositor, _, _, _, _, _, _, _) = test_setup::setup_contract(&env);
+        client.set_claim_grace_period(&depositor, &3600);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #5)")]
+    fn test_claim_before_conditions_met() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_conditional_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+
+        env.ledger().with_mut(|li| li.timestamp = GRACE_PERIOD);
+        client.release_funds(&escrow_id, &beneficiary);
+    }
+
+    #[test]
+    fn test_depositor_refunds_after_timeout() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_conditional_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        let timeout_time = client.get_escrow(&escrow_id).timeout_time;
+
+        env.ledger().with_mut(|li| li.timestamp = timeout_time - 1);
+        let result = client.try_refund_deposit(&escrow_id, &depositor);
+        assert_eq!(result, Err(Ok(EscrowError::TimeoutNotReached)));
+
+        env.ledger().with_mut(|li| li.timestamp = timeout_time);
+        client.refund_deposit(&escrow_id, &depositor);
+
+        assert_eq!(check_balance(&env, &asset.token, &depositor), 1000000);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Refunded);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #4)")]
+    fn test_no_refund_once_conditions_met() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let escrow_id =
+            create_conditional_escrow(&env, &client, &depositor, &beneficiary, &arbitrator, &asset);
+        client.verify_condition(&escrow_id, &arbitrator, &0);
+
+        let timeout_time = client.get_escrow(&escrow_id).timeout_time;
+        env.ledger().with_mut(|li| li.timestamp = timeout_time);
+        client.refund_deposit(&escrow_id, &depositor);
+    }
+}
+
+mod test_htlc {
+    use super::*;
+    use crate::testutils::create_htlc_condition;
+    use soroban_sdk::Bytes;
+
+    // Create a funded escrow in custody locked under the hash of the preimage
+    // until the timelock
+    #[allow(clippy::too_many_arguments)]
+    fn create_htlc_escrow(
+        env: &Env,
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+        preimage: &Bytes,
+        timelock: u64,
+    ) -> u64 {
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.deposit_funds(&escrow_id, depositor, &1000);
+        let hashlock = env.crypto().sha256(preimage).to_bytes();
+        client.add_release_condition(
+            &escrow_id,
+            arbitrator,
+            &create_htlc_condition(env, &hashlock, timelock),
+        );
+        escrow_id
+    }
+
+    #[test]
+    fn test_preimage_releases_escrow() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let preimage = Bytes::from_slice(&env, b"remittance secret");
+        let escrow_id = create_htlc_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            &preimage,
+            3600,
+        );
+
+        client.claim_with_preimage(&escrow_id, &beneficiary, &0, &preimage);
+
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Released);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #30)")]
+    fn test_wrong_preimage() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let preimage = Bytes::from_slice(&env, b"remittance secret");
+        let escrow_id = create_htlc_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            &preimage,
+            3600,
+        );
+
+        let guess = Bytes::from_slice(&env, b"guess");
+        client.claim_with_preimage(&escrow_id, &beneficiary, &0, &guess);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #29)")]
+    fn test_claim_after_timelock() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let preimage = Bytes::from_slice(&env, b"remittance secret");
+        let escrow_id = create_htlc_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            &preimage,
+            3600,
+        );
+
+        env.ledger().with_mut(|li| li.timestamp = 3600);
+        client.claim_with_preimage(&escrow_id, &beneficiary, &0, &preimage);
+    }
+
+    #[test]
+    fn test_depositor_reclaims_after_timelock() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let preimage = Bytes::from_slice(&env, b"remittance secret");
+        let escrow_id = create_htlc_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            &preimage,
+            3600,
+        );
+
+        // The timelock falls well before the escrow timeout
+        env.ledger().with_mut(|li| li.timestamp = 3599);
+        let result = client.try_refund_deposit(&escrow_id, &depositor);
+        assert_eq!(result, Err(Ok(EscrowError::TimeoutNotReached)));
+
+        env.ledger().with_mut(|li| li.timestamp = 3600);
+        client.refund_deposit(&escrow_id, &depositor);
+
+        assert_eq!(check_balance(&env, &asset.token, &depositor), 1000000);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Refunded);
+    }
+
+    #[test]
+    fn test_hashlock_cannot_be_verified() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let preimage = Bytes::from_slice(&env, b"remittance secret");
+        let escrow_id = create_htlc_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            &preimage,
+            3600,
+        );
+
+        let result = client.try_verify_condition(&escrow_id, &arbitrator, &0);
+        assert_eq!(result, Err(Ok(EscrowError::ConditionLocked)));
+        assert_eq!(client.evaluate_conditions(&escrow_id), 0);
+    }
+
+    #[test]
+    fn test_preimage_waits_for_other_conditions() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let preimage = Bytes::from_slice(&env, b"remittance secret");
+        let escrow_id = create_htlc_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            &preimage,
+            3600,
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_condition(&env, "Compliance check", ConditionType::ManualVerification),
+        );
+
+        client.claim_with_preimage(&escrow_id, &beneficiary, &0, &preimage);
+        assert_eq!(client.get_escrow_status(&escrow_id), EscrowStatus::Funded);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 0);
+
+        // A revealed hashlock no longer lets the depositor reclaim early
+        env.ledger().with_mut(|li| li.timestamp = 3600);
+        let result = client.try_refund_deposit(&escrow_id, &depositor);
+        assert_eq!(result, Err(Ok(EscrowError::TimeoutNotReached)));
+
+        client.verify_condition(&escrow_id, &arbitrator, &1);
+        client.release_funds(&escrow_id, &arbitrator);
+        assert_eq!(check_balance(&env, &asset.token, &beneficiary), 1000);
+    }
+}
+
+mod test_attestations {
+    use super::*;
+    use crate::testutils::{create_attestation_condition, sign_message};
+    use ed25519_dalek::SigningKey;
+    use soroban_sdk::Bytes;
+
+    // Create a funded escrow in custody gated on a delivery attestation
+    fn create_attested_escrow(
+        env: &Env,
+        client: &EscrowContractClient,
+        depositor: &Address,
+        beneficiary: &Address,
+        arbitrator: &Address,
+        asset: &Asset,
+        attester: &SigningKey,
+    ) -> u64 {
+        let escrow_id = client.create_escrow(
+            depositor,
+            beneficiary,
+            arbitrator,
+            &None,
+            asset,
+            &1000,
+            &(17280 * 7),
+        );
+        client.deposit_funds(&escrow_id, depositor, &1000);
+        client.add_release_condition(
+            &escrow_id,
+            arbitrator,
+            &create_attestation_condition(env, attester, "delivered"),
+        );
+        escrow_id
+    }
+
+    #[test]
+    fn test_attestation_fulfils_condition() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let attester = SigningKey::from_bytes(&[7; 32]);
+        let escrow_id = create_attested_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            &attester,
+        );
+
+        let message = client.get_attestation_message(&escrow_id, &0);
+        let mut expected = Bytes::from_slice(&env, b"delivered");
+        expected.extend_from_array(&escrow_id.to_be_bytes());
+        expected.extend_from_array(&0u32.to_be_bytes());
+        assert_eq!(message, expected);
+
+        // Submitted without any party authorizing it
+        env.set_auths(&[]);
+        client.submit_attestation(&escrow_id, &0, &sign_message(&env, &attester, &message));
+
+        assert!(
+            client
+                .get_release_conditions(&escrow_id)
+                .get(0)
+                .unwrap()
+                .is_fulfilled
+        );
+        assert_eq!(
+            client.get_escrow_status(&escrow_id),
+            EscrowStatus::ConditionsMet
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Crypto, InvalidInput)")]
+    fn test_attestation_by_other_key() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let attester = SigningKey::from_bytes(&[7; 32]);
+        let escrow_id = create_attested_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            &attester,
+        );
+
+        let message = client.get_attestation_message(&escrow_id, &0);
+        let impostor = SigningKey::from_bytes(&[8; 32]);
+        client.submit_attestation(&escrow_id, &0, &sign_message(&env, &impostor, &message));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Crypto, InvalidInput)")]
+    fn test_attestation_replayed_on_other_escrow() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let attester = SigningKey::from_bytes(&[7; 32]);
+        let first = create_attested_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            &attester,
+        );
+        let second = create_attested_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            &attester,
+        );
+
+        let message = client.get_attestation_message(&first, &0);
+        let signature = sign_message(&env, &attester, &message);
+        client.submit_attestation(&first, &0, &signature);
+        client.submit_attestation(&second, &0, &signature);
+    }
+
+    #[test]
+    fn test_attestation_on_other_condition() {
+        let env = Env::default();
+        let (client, depositor, beneficiary, arbitrator, _, asset, _, _, _) =
+            test_setup::setup_contract(&env);
+        let attester = SigningKey::from_bytes(&[7; 32]);
+        let escrow_id = create_attested_escrow(
+            &env,
+            &client,
+            &depositor,
+            &beneficiary,
+            &arbitrator,
+            &asset,
+            &attester,
+        );
+        client.add_release_condition(
+            &escrow_id,
+            &arbitrator,
+            &create_condition(&env, "Inspection", ConditionType::ManualVerification),
+        );
+
+        let message = client.get_attestation_message(&escrow_id, &0);
+        let signature = sign_message(&env, &attester, &message);
+        let result = client.try_submit_attestation(&escrow_id, &1, &signature);
+        assert_eq!(result, Err(Ok(EscrowError::InvalidCondition)));
+
+        client.submit_attestation(&escrow_id, &0, &signature);
+        let result = client.try_submit_attestation(&escrow_id, &0, &signature);
+        assert_eq!(result, Err(Ok(EscrowError::AlreadyFulfilled)));
     }
 }
@@ -0,0 +1,139 @@
+#![cfg(test)]
+extern crate std;
+
+use crate::types::{Asset, Condition, ConditionPayout, ConditionTrigger, ConditionType};
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::{
+    contract, contractimpl,
+    token::{StellarAssetClient as TokenAdmin, TokenClient},
+    Address, Bytes, BytesN, Env, String, Vec,
+};
+
+// Oracle stand-in: attests whatever keys it has been told are true
+#[contract]
+pub struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    pub fn set(env: Env, key: Bytes, value: bool) {
+        env.storage().instance().set(&key, &value);
+    }
+
+    pub fn attest(env: Env, key: Bytes) -> bool {
+        env.storage().instance().get(&key).unwrap_or(false)
+    }
+}
+
+// Helper to create a token contract
+pub(crate) fn create_token_contract<'a>(e: &Env, admin: &Address) -> (Address, TokenAdmin<'a>) {
+    let token_contract_id = e.register_stellar_asset_contract_v2(admin.clone());
+    let token = TokenAdmin::new(e, &token_contract_id.address());
+    (token_contract_id.address(), token)
+}
+
+// Helper to mint tokens to an address
+pub(crate) fn mint_tokens(token: &TokenAdmin, to: &Address, amount: &i128) {
+    token.mint(to, amount);
+}
+
+// Helper to check token balance
+pub(crate) fn check_balance(e: &Env, token_address: &Address, account: &Address) -> i128 {
+    let token_client = TokenClient::new(e, token_address);
+    token_client.balance(account)
+}
+
+// Helper to describe the escrowed token
+pub(crate) fn create_asset(e: &Env, token_address: &Address) -> Asset {
+    Asset {
+        token: token_address.clone(),
+        symbol: String::from_str(e, "TKN"),
+        decimals: 7,
+    }
+}
+
+// Helper to build an unfulfilled release condition
+pub(crate) fn create_condition(
+    e: &Env,
+    description: &str,
+    condition_type: ConditionType,
+) -> Condition {
+    Condition {
+        condition_type,
+        description: String::from_str(e, description),
+        verification_method: String::from_str(e, "manual"),
+        is_fulfilled: false,
+        payout: ConditionPayout::None,
+        trigger: ConditionTrigger::None,
+    }
+}
+
+// Helper to build a milestone that pays out when verified
+pub(crate) fn create_milestone(e: &Env, description: &str, payout: ConditionPayout) -> Condition {
+    Condition {
+        payout,
+        ..create_condition(e, description, ConditionType::ManualVerification)
+    }
+}
+
+// Helper to build a time-based condition
+pub(crate) fn create_time_condition(e: &Env, trigger: ConditionTrigger) -> Condition {
+    Condition {
+        trigger,
+        ..create_condition(e, "Unlock", ConditionType::TimeBased)
+    }
+}
+
+// Helper to build an oracle condition for a query key
+pub(crate) fn create_oracle_condition(e: &Env, oracle: &Address, key: &str) -> Condition {
+    Condition {
+        trigger: ConditionTrigger::Oracle(oracle.clone(), Bytes::from_slice(e, key.as_bytes())),
+        verification_method: String::from_str(e, "oracle"),
+        ..create_condition(e, "Oracle attestation", ConditionType::ExternalOracle)
+    }
+}
+
+// Helper to build a MultiSig condition
+pub(crate) fn create_multisig_condition(
+    e: &Env,
+    signers: &Vec<Address>,
+    threshold: u32,
+) -> Condition {
+    Condition {
+        trigger: ConditionTrigger::MultiSig(signers.clone(), threshold),
+        verification_method: String::from_str(e, "multisig"),
+        ..create_condition(e, "Signer approval", ConditionType::MultiSig)
+    }
+}
+
+// Helper to build a hash-time-locked condition
+pub(crate) fn create_htlc_condition(e: &Env, hashlock: &BytesN<32>, timelock: u64) -> Condition {
+    Condition {
+        trigger: ConditionTrigger::HashLock(hashlock.clone(), timelock),
+        verification_method: String::from_str(e, "preimage"),
+        ..create_condition(e, "Hashlock", ConditionType::HashTimeLock)
+    }
+}
+
+// Helper to build a signed-attestation condition for an attester's key
+pub(crate) fn create_attestation_condition(
+    e: &Env,
+    attester: &SigningKey,
+    template: &str,
+) -> Condition {
+    let public_key = BytesN::from_array(e, &attester.verifying_key().to_bytes());
+    Condition {
+        trigger: ConditionTrigger::Attestation(
+            public_key,
+            Bytes::from_slice(e, template.as_bytes()),
+        ),
+        verification_method: String::from_str(e, "ed25519"),
+        ..create_condition(e, "Delivery attestation", ConditionType::SignedAttestation)
+    }
+}
+
+// Helper to sign a message with an attester's key
+pub(crate) fn sign_message(e: &Env, attester: &SigningKey, message: &Bytes) -> BytesN<64> {
+    let mut buf = std::vec![0u8; message.len() as usize];
+    message.copy_into_slice(&mut buf);
+    BytesN::from_array(e, &attester.sign(&buf).to_bytes())
+}
@@ -1,4 +1,4 @@
-use soroban_sdk::{contracterror, contracttype, Address, String};
+use soroban_sdk::{contracterror, contracttype, Address, Bytes, BytesN, String, Vec};
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -19,6 +19,30 @@ pub enum ConditionType {
     ManualVerification,
     ExternalOracle,
     MultiSig,
+    HashTimeLock,
+    SignedAttestation,
+}
+
+// What verifying a condition pays out to the beneficiary right away
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConditionPayout {
+    None,         // Gate only; funds move with the final release
+    Amount(i128), // Fixed milestone amount
+    Share(i128),  // Basis points of the escrow amount (10000 = 100%)
+}
+
+// How a condition is fulfilled other than by the arbitrator verifying it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConditionTrigger {
+    None,                           // Verified by the arbitrator
+    UnlockTime(u64),                // TimeBased: fulfilled once the ledger timestamp reaches this
+    UnlockLedger(u32),              // TimeBased: fulfilled once the ledger sequence reaches this
+    Oracle(Address, Bytes),         // ExternalOracle: fulfilled once the oracle attests the key
+    MultiSig(Vec<Address>, u32),    // MultiSig: fulfilled once this many of the signers approve
+    HashLock(BytesN<32>, u64),      // HashTimeLock: fulfilled by its preimage before this timestamp
+    Attestation(BytesN<32>, Bytes), // SignedAttestation: fulfilled once this ed25519 key signs
 }
 
 #[contracttype]
@@ -28,6 +52,8 @@ pub struct Condition {
     pub description: String,
     pub verification_method: String,
     pub is_fulfilled: bool,
+    pub payout: ConditionPayout,
+    pub trigger: ConditionTrigger,
 }
 
 #[contracttype]
@@ -71,6 +97,53 @@ pub struct DisputeProcess {
     pub reason: String,
     pub is_active: bool,
     pub outcome: DisputeOutcomeOption,
+    pub ruling: DisputeOutcomeOption, // Ruling waiting out the appeal window
+    pub voting_ends: u64,             // End of the panel's voting window, 0 without one
+    pub evidence_ends: u64,           // End of the evidence period
+    pub appeal_ends: u64,             // End of the appeal window of the ruling
+    pub appealed: bool,               // Whether the appeal panel decides
+}
+
+// A document a party submits in support of their side of a dispute
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Evidence {
+    pub submitter: Address,
+    pub digest: BytesN<32>, // Hash of the document
+    pub uri: String,        // Where the document can be fetched
+    pub submitted_at: u64,
+}
+
+// Evidence and appeal timing of an escrow's disputes. The parties submit
+// evidence until the evidence period ends; a ruling then waits out the appeal
+// window before any funds move, and an appeal hands the dispute to the
+// appeal panel, whose decision is final.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisputeTerms {
+    pub evidence_period: u64, // Seconds the parties have to submit evidence
+    pub appeal_window: u64,   // Seconds a ruling can be appealed in
+    pub appeal_panel: ArbitrationPanel,
+}
+
+// Share of the whole panel an outcome needs to win
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DecisionRule {
+    Majority,      // More than half of the members
+    Supermajority, // At least two thirds of the members
+}
+
+// Arbitrators deciding the disputes of an escrow by vote. Members who do not
+// vote count against every outcome; when no outcome reaches the rule's share,
+// including on a tie, the fallback outcome is executed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ArbitrationPanel {
+    pub members: Vec<Address>,
+    pub rule: DecisionRule,
+    pub voting_period: u64, // Seconds the panel has to vote once a dispute starts
+    pub fallback: DisputeOutcome,
 }
 
 #[contracttype]
@@ -81,6 +154,85 @@ pub struct Asset {
     pub decimals: u32,
 }
 
+// Another asset locked alongside the escrow's own. A leg is deposited and
+// paid out in step with the escrow amount, pro rata to its own amount.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BasketLeg {
+    pub asset: Asset,
+    pub amount: i128,
+    pub funded: i128,   // Amount of this leg deposited so far
+    pub paid_out: i128, // Amount of this leg paid out so far to either party
+}
+
+// Fees an escrow pays to the fee collector, fixed when it is created. The
+// platform fee is taken from everything paid to the beneficiary and the
+// arbitrator fee from the funds left when a dispute is resolved. The dispute
+// surcharge is charged to the party the resolution goes against: it comes out
+// of the disputed funds before the winner is paid, from the loser's own share
+// first when a partial release leaves them one. Even splits pay no surcharge.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeSchedule {
+    pub fee_collector: Address,
+    pub platform_fee_bps: i128, // Basis points (10000 = 100%)
+    pub arbitrator_fee: i128,
+    pub dispute_surcharge: i128,
+}
+
+// A change to a live escrow that one party proposes and the other approves
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AmendmentChange {
+    Amount(i128),          // New escrow amount; the difference is topped up or refunded
+    ExtendTimeout(u64),    // New timeout time, later than the current one
+    ClaimGracePeriod(u64), // Seconds the beneficiary waits before claiming directly
+    Arbitrator(Address),   // Arbitrator taking over the escrow
+    RemoveCondition(u32),  // Index of an unfulfilled release condition to drop
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AmendmentStatus {
+    Proposed,
+    Approved,
+    Rejected,
+}
+
+// One version in an escrow's amendment history
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Amendment {
+    pub version: u32,
+    pub proposer: Address,
+    pub change: AmendmentChange,
+    pub status: AmendmentStatus,
+    pub proposed_at: u64,
+    pub decided_at: u64, // 0 while the amendment is still proposed
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Escrow {
+    pub id: u64,
+    pub depositor: Address,
+    pub beneficiary: Address,
+    pub arbitrator: Address,
+    pub deposit_account: Option<Address>, // None when the contract holds the funds itself
+    pub asset: Asset,
+    pub amount: i128,
+    pub funded: i128,            // Amount the depositor has deposited so far
+    pub held: i128,              // Funds the contract currently holds for this escrow
+    pub released: i128,          // Amount already paid out to the beneficiary
+    pub paid_out: i128,          // Amount already paid out to either party
+    pub legs: Vec<BasketLeg>,    // Other assets locked alongside this one
+    pub conditions_met_at: u64,  // When every condition was met, 0 until then
+    pub claim_grace_period: u64, // Seconds after that before the beneficiary can claim
+    pub timeout_time: u64,
+    pub status: EscrowStatus,
+    pub created_at: u64,
+}
+
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum EscrowError {
@@ -95,20 +247,47 @@ pub enum EscrowError {
     TimeoutNotReached = 9,
     NoDispute = 10,
     NotInitialized = 11,
+    ApprovalRequired = 12,
+    EscrowNotFound = 13,
+    ConditionLocked = 14,
+    AlreadyApproved = 15,
+    NotApproved = 16,
+    VotingClosed = 17,
+    VotingOpen = 18,
+    InvalidPanel = 19,
+    EvidenceClosed = 20,
+    EvidenceOpen = 21,
+    AppealOpen = 22,
+    AppealClosed = 23,
+    DuplicateAsset = 24,
+    AmendmentPending = 25,
+    NoAmendment = 26,
+    InvalidAmendment = 27,
+    GracePeriodActive = 28,
+    HashlockExpired = 29,
+    InvalidPreimage = 30,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DataKey {
-    Admin,             // Admin address
-    Depositor,         // Depositor address
-    Beneficiary,       // Beneficiary address
-    Arbitrator,        // Arbitrator address
-    DepositAccount,    // Deposit account address
-    Asset,             // Asset details
-    Amount,            // Escrow amount
-    ReleaseConditions, // List of release conditions
-    TimeoutTime,       // Timeout timestamp
-    DisputeResolution, // Dispute process details
-    Status,            // Escrow status
+    Admin,                        // Admin address
+    ApprovalManager,              // Optional approval manager gating releases
+    FeeSchedule,                  // Fee schedule new escrows are created with
+    ClaimGracePeriod,             // Claim grace period new escrows are created with
+    EscrowCount,                  // Number of escrows created so far
+    Escrow(u64),                  // Escrow details by ID
+    ReleaseConditions(u64),       // List of release conditions of an escrow
+    DisputeResolution(u64),       // Dispute process details of an escrow
+    DepositorEscrows(Address),    // IDs of the escrows of a depositor
+    BeneficiaryEscrows(Address),  // IDs of the escrows of a beneficiary
+    ArbitratorEscrows(Address),   // IDs of the escrows of an arbitrator
+    ConditionApprovals(u64, u32), // Signers approving a MultiSig condition of an escrow
+    Fees(u64),                    // Fee schedule of an escrow
+    FeeBalance(Address),          // Fees collected so far per token
+    ArbitrationPanel(u64),        // Panel deciding the disputes of an escrow
+    DisputeVotes(u64),            // Votes of the panel members on an escrow's dispute
+    DisputeTerms(u64),            // Evidence and appeal timing of an escrow's disputes
+    DisputeEvidence(u64),         // Evidence submitted in an escrow's dispute
+    Amendments(u64),              // Amendment history of an escrow
 }
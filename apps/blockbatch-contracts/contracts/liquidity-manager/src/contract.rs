@@ -196,6 +196,7 @@ impl LiquidityManager {
         asset_address: Address,
         amount: i128,
         depositor: Address,
+        idempotency_key: Option<BytesN<32>>,
     ) -> Result<(), Error> {
         // Require depositor authorization
         depositor.require_auth();
@@ -210,6 +211,7 @@ impl LiquidityManager {
                     asset_address.into_val(&env),
                     amount.into_val(&env),
                     depositor.into_val(&env),
+                    idempotency_key.into_val(&env),
                 ],
             ),
         );
@@ -224,6 +226,7 @@ impl LiquidityManager {
         asset_address: Address,
         amount: i128,
         depositor: Address,
+        idempotency_key: Option<BytesN<32>>,
     ) -> Result<(), Error> {
         // Require depositor authorization
         depositor.require_auth();
@@ -238,6 +241,7 @@ impl LiquidityManager {
                     asset_address.into_val(&env),
                     amount.into_val(&env),
                     depositor.into_val(&env),
+                    idempotency_key.into_val(&env),
                 ],
             ),
         );
@@ -148,7 +148,7 @@ mod test_pool_creation {
         );
 
         // Add liquidity
-        contract.add_liquidity(&pool_address, &token_address, &5000, &depositor);
+        contract.add_liquidity(&pool_address, &token_address, &5000, &depositor, &None);
 
         // Verify the token was transferred to the pool
         let pool_balance = check_balance(&env, &token_address, &pool_address);
@@ -1,3 +1,6 @@
 // Define fee constants
 pub const WITHDRAWAL_FEE_BASIS_POINTS: i128 = 10; // 0.1% (10 basis points)
 pub const BASIS_POINTS_DENOMINATOR: i128 = 10000; // 100% = 10000 basis points
+
+// Define idempotency constants
+pub const IDEMPOTENCY_TTL_LEDGERS: u32 = 17280; // ~1 day of 5 second ledgers
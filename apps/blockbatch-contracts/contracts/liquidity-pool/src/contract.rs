@@ -1,6 +1,9 @@
 use crate::constants::*;
-use crate::types::{AlertType, Asset, DataKey as PoolDataKey, DepositorBalance, Error, Thresholds};
-use soroban_sdk::{contract, contractimpl, token, Address, Env, Symbol, Vec};
+use crate::types::{
+    AlertType, Asset, DataKey as PoolDataKey, DepositorBalance, Error, PayoutOperator, Thresholds,
+};
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{contract, contractimpl, token, Address, BytesN, Env, IntoVal, Symbol, Vec};
 
 #[contract]
 pub struct LiquidityPool;
@@ -107,10 +110,16 @@ impl LiquidityPool {
         asset_address: Address,
         amount: i128,
         depositor: Address,
+        idempotency_key: Option<BytesN<32>>,
     ) -> Result<(), Error> {
         // Require depositor authorization
         depositor.require_auth();
 
+        // A key that was already processed returns without running again
+        if Self::is_processed(&env, "add_liquidity", &depositor, &idempotency_key) {
+            return Ok(());
+        }
+
         // Verify asset exists in the pool
         let assets = env
             .storage()
@@ -163,6 +172,9 @@ impl LiquidityPool {
             new_depositor_balance.clone(),
         );
 
+        // Remember the idempotency key
+        Self::mark_processed(&env, "add_liquidity", &depositor, &idempotency_key);
+
         Ok(())
     }
 
@@ -172,10 +184,21 @@ impl LiquidityPool {
         asset_address: Address,
         amount: i128,
         depositor: Address,
+        idempotency_key: Option<BytesN<32>>,
     ) -> Result<(), Error> {
         // Require depositor authorization
         depositor.require_auth();
 
+        // A key that was already processed returns without running again
+        if Self::is_processed(
+            &env,
+            "withdraw_deposited_tokens",
+            &depositor,
+            &idempotency_key,
+        ) {
+            return Ok(());
+        }
+
         // Verify asset exists in the pool
         let assets = env
             .storage()
@@ -295,6 +318,14 @@ impl LiquidityPool {
                 .set(&fee_balance_key, &new_fee_balance);
         }
 
+        // Remember the idempotency key
+        Self::mark_processed(
+            &env,
+            "withdraw_deposited_tokens",
+            &depositor,
+            &idempotency_key,
+        );
+
         Ok(())
     }
 
@@ -327,6 +358,7 @@ impl LiquidityPool {
         env: Env,
         asset_address: Address,
         amount: i128,
+        idempotency_key: Option<BytesN<32>>,
     ) -> Result<(), Error> {
         // Verify caller is admin
         Self::verify_admin(&env)?;
@@ -337,6 +369,11 @@ impl LiquidityPool {
             .get(&PoolDataKey::Admin)
             .ok_or(Error::Unauthorized)?;
 
+        // A key that was already processed returns without running again
+        if Self::is_processed(&env, "admin_withdraw_funds", &admin, &idempotency_key) {
+            return Ok(());
+        }
+
         // Verify asset exists in the pool
         let assets = env
             .storage()
@@ -385,6 +422,9 @@ impl LiquidityPool {
             .get::<PoolDataKey, Address>(&PoolDataKey::FeeCollector)
             .ok_or(Error::Unauthorized)?;
 
+        // Consume the maker-checker approval for this exact withdrawal, if required
+        Self::consume_approval(&env, Self::withdrawal_payload(&env, &asset_address, amount))?;
+
         // Transfer the withdrawal amount to the stored admin (retrieved above)
         token_client.transfer(&env.current_contract_address(), &admin, &withdrawal_amount);
 
@@ -407,9 +447,244 @@ impl LiquidityPool {
                 .set(&fee_balance_key, &new_fee_balance);
         }
 
+        // Remember the idempotency key
+        Self::mark_processed(&env, "admin_withdraw_funds", &admin, &idempotency_key);
+
         Ok(())
     }
 
+    // Require an approved request from an approval manager before admin withdrawals
+    pub fn set_approval_manager(env: Env, manager: Option<Address>) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Store or clear the approval manager
+        match manager {
+            Some(manager) => env
+                .storage()
+                .persistent()
+                .set(&PoolDataKey::ApprovalManager, &manager),
+            None => env
+                .storage()
+                .persistent()
+                .remove(&PoolDataKey::ApprovalManager),
+        }
+
+        Ok(())
+    }
+
+    // Get the payload an admin withdrawal has to be approved with
+    pub fn get_withdrawal_payload(env: Env, asset_address: Address, amount: i128) -> BytesN<32> {
+        Self::withdrawal_payload(&env, &asset_address, amount)
+    }
+
+    // Authorize an operator, such as a batch payment contract, to pay out up to
+    // `limit` of an asset per `period` seconds directly from the pool
+    pub fn set_payout_operator(
+        env: Env,
+        operator: Address,
+        asset_address: Address,
+        limit: i128,
+        period: u64,
+    ) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Verify limit and period are valid
+        if limit <= 0 || period == 0 {
+            return Err(Error::InvalidAllocation);
+        }
+
+        // Verify asset exists in the pool
+        let assets = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Vec<Asset>>(&PoolDataKey::Assets)
+            .ok_or(Error::AssetNotFound)?;
+
+        let asset_found = assets.iter().any(|asset| asset.token == asset_address);
+        if !asset_found {
+            return Err(Error::AssetNotFound);
+        }
+
+        // Store the allowance, starting a fresh period
+        let payout_operator = PayoutOperator {
+            limit,
+            period,
+            period_start: env.ledger().timestamp(),
+            spent: 0,
+        };
+        env.storage().persistent().set(
+            &PoolDataKey::PayoutOperator(operator.clone(), asset_address.clone()),
+            &payout_operator,
+        );
+
+        // Emit event for the new operator
+        env.events().publish(
+            (
+                Symbol::new(&env, "payout_operator_set"),
+                operator,
+                asset_address,
+            ),
+            (limit, period),
+        );
+
+        Ok(())
+    }
+
+    // Revoke an operator's payout allowance for an asset
+    pub fn remove_payout_operator(
+        env: Env,
+        operator: Address,
+        asset_address: Address,
+    ) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::PayoutOperator(
+                operator.clone(),
+                asset_address.clone(),
+            ));
+
+        // Emit event for the removed operator
+        env.events().publish(
+            (
+                Symbol::new(&env, "payout_operator_removed"),
+                operator,
+                asset_address,
+            ),
+            (),
+        );
+
+        Ok(())
+    }
+
+    // Pay a recipient from pool liquidity on behalf of an authorized operator
+    pub fn operator_payout(
+        env: Env,
+        operator: Address,
+        asset_address: Address,
+        recipient: Address,
+        amount: i128,
+        idempotency_key: Option<BytesN<32>>,
+    ) -> Result<(), Error> {
+        // Require operator authorization
+        operator.require_auth();
+
+        // A key that was already processed returns without running again
+        if Self::is_processed(&env, "operator_payout", &operator, &idempotency_key) {
+            return Ok(());
+        }
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Get the operator's allowance, rolling over to a new period if needed
+        let operator_key = PoolDataKey::PayoutOperator(operator.clone(), asset_address.clone());
+        let mut payout_operator = Self::current_payout_operator(&env, &operator_key)?;
+
+        // Check the per-period limit
+        if payout_operator.spent + amount > payout_operator.limit {
+            return Err(Error::PayoutLimitExceeded);
+        }
+
+        // Check current token balance using token client
+        let token_client = token::Client::new(&env, &asset_address);
+        let current_balance = token_client.balance(&env.current_contract_address());
+
+        if current_balance < amount {
+            return Err(Error::InsufficientBalance);
+        }
+
+        // Get thresholds for this asset
+        let threshold_key = PoolDataKey::Thresholds(asset_address.clone());
+        let thresholds = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Thresholds>(&threshold_key)
+            .unwrap_or(Thresholds {
+                min_threshold: 0,
+                alert_threshold: 0,
+            });
+
+        // Payouts may never take the pool below its minimum threshold
+        let new_balance = current_balance - amount;
+        if new_balance < thresholds.min_threshold {
+            return Err(Error::BelowMinThreshold);
+        }
+
+        // Record the spend before transferring
+        payout_operator.spent += amount;
+        env.storage()
+            .persistent()
+            .set(&operator_key, &payout_operator);
+
+        token_client.transfer(&env.current_contract_address(), &recipient, &amount);
+
+        // Remember the idempotency key
+        Self::mark_processed(&env, "operator_payout", &operator, &idempotency_key);
+
+        // Emit event for the payout
+        env.events().publish(
+            (
+                Symbol::new(&env, "operator_payout"),
+                operator,
+                asset_address.clone(),
+            ),
+            (recipient, amount),
+        );
+
+        // Emit an alert if this payout took the balance across the alert threshold
+        if thresholds.alert_threshold > 0
+            && current_balance > thresholds.alert_threshold
+            && new_balance <= thresholds.alert_threshold
+        {
+            let alert_type =
+                if thresholds.min_threshold > 0 && new_balance <= thresholds.min_threshold {
+                    AlertType::CriticalLiquidity
+                } else {
+                    AlertType::LowLiquidity
+                };
+            env.events().publish(
+                (Symbol::new(&env, "alert"), asset_address),
+                (alert_type, new_balance),
+            );
+        }
+
+        Ok(())
+    }
+
+    // Get an operator's payout allowance, with the current period applied
+    pub fn get_payout_operator(
+        env: Env,
+        operator: Address,
+        asset_address: Address,
+    ) -> Result<PayoutOperator, Error> {
+        Self::current_payout_operator(&env, &PoolDataKey::PayoutOperator(operator, asset_address))
+    }
+
+    // Get how much an operator can still pay out now, considering both its
+    // remaining limit and the pool's minimum threshold
+    pub fn get_available_payout(
+        env: Env,
+        operator: Address,
+        asset_address: Address,
+    ) -> Result<i128, Error> {
+        let payout_operator = Self::current_payout_operator(
+            &env,
+            &PoolDataKey::PayoutOperator(operator, asset_address.clone()),
+        )?;
+        let (balance, thresholds) = Self::get_liquidity_status(env, asset_address)?;
+
+        let remaining_limit = payout_operator.limit - payout_operator.spent;
+        let above_minimum = (balance - thresholds.min_threshold).max(0);
+
+        Ok(remaining_limit.min(above_minimum))
+    }
+
     // Check liquidity levels and return true if any alert threshold is crossed
     pub fn check_liquidity_levels(env: Env) -> Result<bool, Error> {
         // Get assets
@@ -564,6 +839,100 @@ impl LiquidityPool {
         Ok(allocation)
     }
 
+    // Load an operator's allowance, starting a new period once the current one has ended
+    fn current_payout_operator(
+        env: &Env,
+        operator_key: &PoolDataKey,
+    ) -> Result<PayoutOperator, Error> {
+        let mut payout_operator = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, PayoutOperator>(operator_key)
+            .ok_or(Error::Unauthorized)?;
+
+        let now = env.ledger().timestamp();
+        if now >= payout_operator.period_start + payout_operator.period {
+            let elapsed_periods = (now - payout_operator.period_start) / payout_operator.period;
+            payout_operator.period_start += elapsed_periods * payout_operator.period;
+            payout_operator.spent = 0;
+        }
+
+        Ok(payout_operator)
+    }
+
+    // Whether `caller` already made a successful call to `function` with this key
+    fn is_processed(
+        env: &Env,
+        function: &str,
+        caller: &Address,
+        idempotency_key: &Option<BytesN<32>>,
+    ) -> bool {
+        match idempotency_key {
+            Some(key) => env.storage().temporary().has(&PoolDataKey::Processed(
+                Symbol::new(env, function),
+                caller.clone(),
+                key.clone(),
+            )),
+            None => false,
+        }
+    }
+
+    // Record a processed key in temporary storage so that it expires with its TTL
+    fn mark_processed(
+        env: &Env,
+        function: &str,
+        caller: &Address,
+        idempotency_key: &Option<BytesN<32>>,
+    ) {
+        if let Some(key) = idempotency_key {
+            let processed_key =
+                PoolDataKey::Processed(Symbol::new(env, function), caller.clone(), key.clone());
+            env.storage().temporary().set(&processed_key, &true);
+            env.storage().temporary().extend_ttl(
+                &processed_key,
+                IDEMPOTENCY_TTL_LEDGERS,
+                IDEMPOTENCY_TTL_LEDGERS,
+            );
+        }
+    }
+
+    // Hash of the withdrawal arguments, binding an approval to one asset and amount
+    fn withdrawal_payload(env: &Env, asset_address: &Address, amount: i128) -> BytesN<32> {
+        let action = (
+            Symbol::new(env, "admin_withdraw_funds"),
+            asset_address.clone(),
+            amount,
+        );
+        env.crypto().sha256(&action.to_xdr(env)).to_bytes()
+    }
+
+    // Ask the approval manager, if one is configured, to consume the approval
+    fn consume_approval(env: &Env, payload: BytesN<32>) -> Result<(), Error> {
+        let manager = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Address>(&PoolDataKey::ApprovalManager);
+
+        if let Some(manager) = manager {
+            let result = env.try_invoke_contract::<u64, soroban_sdk::Error>(
+                &manager,
+                &Symbol::new(env, "consume"),
+                Vec::from_array(
+                    env,
+                    [
+                        env.current_contract_address().into_val(env),
+                        payload.into_val(env),
+                    ],
+                ),
+            );
+            if !matches!(result, Ok(Ok(_))) {
+                return Err(Error::ApprovalRequired);
+            }
+        }
+
+        Ok(())
+    }
+
     // Admin verification function
     fn verify_admin(env: &Env) -> Result<(), Error> {
         let admin: Address = env
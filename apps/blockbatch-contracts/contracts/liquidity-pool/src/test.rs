@@ -1,6 +1,8 @@
 use super::*;
 use crate::testutils::{calculate_fee, check_balance, create_token_contract, mint_tokens};
 use crate::types::{AlertType, Asset};
+use approval_manager::testutils::{approve_action, register_approval_manager};
+use approval_manager::ApprovalManagerClient;
 use soroban_sdk::testutils::Address as _;
 use soroban_sdk::{
     testutils::Events,
@@ -180,7 +182,7 @@ mod test_liquidity_management {
         );
 
         // Add liquidity
-        contract.add_liquidity(&token1_address, &5000, &depositor);
+        contract.add_liquidity(&token1_address, &5000, &depositor, &None);
 
         // Check depositor balance - first get the full balance object
         let balance = contract.get_depositor_balance(&token1_address, &depositor);
@@ -223,7 +225,7 @@ mod test_liquidity_management {
         );
 
         // Add liquidity
-        contract.add_liquidity(&token1_address, &5000, &depositor);
+        contract.add_liquidity(&token1_address, &5000, &depositor, &None);
 
         // Set thresholds to allow withdrawal
         contract.set_thresholds(&token1_address, &1000, &4000);
@@ -233,7 +235,7 @@ mod test_liquidity_management {
         let initial_fee_collector_balance = check_balance(&env, &token1_address, &fee_collector);
 
         // Withdraw part of the tokens
-        contract.withdraw_deposited_tokens(&token1_address, &3000, &depositor);
+        contract.withdraw_deposited_tokens(&token1_address, &3000, &depositor, &None);
 
         // Verify withdrawal authorization
         let auths = env.auths();
@@ -288,13 +290,13 @@ mod test_liquidity_management {
         );
 
         // Add liquidity
-        contract.add_liquidity(&token1_address, &5000, &depositor);
+        contract.add_liquidity(&token1_address, &5000, &depositor, &None);
 
         // Set thresholds to allow full withdrawal
         contract.set_thresholds(&token1_address, &0, &1000);
 
         // Withdraw all tokens
-        contract.withdraw_deposited_tokens(&token1_address, &5000, &depositor);
+        contract.withdraw_deposited_tokens(&token1_address, &5000, &depositor, &None);
 
         // Check that depositor record was removed
         let result = contract.try_get_depositor_balance(&token1_address, &depositor);
@@ -327,10 +329,10 @@ mod test_liquidity_management {
         );
 
         // Add liquidity
-        contract.add_liquidity(&token1_address, &5000, &depositor);
+        contract.add_liquidity(&token1_address, &5000, &depositor, &None);
 
         // Try to withdraw more than deposited
-        contract.withdraw_deposited_tokens(&token1_address, &6000, &depositor);
+        contract.withdraw_deposited_tokens(&token1_address, &6000, &depositor, &None);
     }
 }
 
@@ -355,14 +357,14 @@ mod test_admin_functions {
         );
 
         // Add liquidity
-        contract.add_liquidity(&token1_address, &5000, &depositor);
+        contract.add_liquidity(&token1_address, &5000, &depositor, &None);
 
         // Get initial admin balance
         let initial_admin_balance = check_balance(&env, &token1_address, &admin);
 
         // Admin withdraws some funds
         let withdraw_amount = 2000;
-        contract.admin_withdraw_funds(&token1_address, &withdraw_amount);
+        contract.admin_withdraw_funds(&token1_address, &withdraw_amount, &None);
 
         // Calculate expected fee and net withdrawal
         let fee = calculate_fee(withdraw_amount);
@@ -409,13 +411,13 @@ mod test_alerts {
             &5000,
             &(env.ledger().sequence() + 5000),
         );
-        contract.add_liquidity(&token1_address, &5000, &depositor);
+        contract.add_liquidity(&token1_address, &5000, &depositor, &None);
 
         // Add liquidity for second token (below alert threshold)
         let _token_client2 = TokenClient::new(&env, &token2_address);
         mint_tokens(&token_admin2, &depositor, &2000);
 
-        contract.add_liquidity(&token2_address, &2000, &depositor);
+        contract.add_liquidity(&token2_address, &2000, &depositor, &None);
 
         // Check liquidity levels - should return true indicating alerts are needed
         let result = contract.check_liquidity_levels();
@@ -440,3 +442,339 @@ mod test_alerts {
         assert!(!events.is_empty());
     }
 }
+
+mod test_admin_approval {
+    use super::*;
+
+    // Fund the pool and put an approval manager in front of admin withdrawals
+    fn setup_gated_pool<'a>(
+        env: &Env,
+        contract: &LiquidityPoolClient,
+        token_address: &Address,
+    ) -> ApprovalManagerClient<'a> {
+        let depositor = Address::generate(env);
+        let token_admin = TokenAdmin::new(env, token_address);
+        mint_tokens(&token_admin, &depositor, &10000);
+        contract.add_liquidity(token_address, &5000, &depositor, &None);
+
+        let manager = register_approval_manager(env, &contract.address);
+        contract.set_approval_manager(&Some(manager.address.clone()));
+        manager
+    }
+
+    #[test]
+    fn test_admin_withdraw_approved() {
+        let env = Env::default();
+        let (contract, admin, _, token1_address, _) = test_setup::setup_contract(&env);
+        let manager = setup_gated_pool(&env, &contract, &token1_address);
+        let initial_admin_balance = check_balance(&env, &token1_address, &admin);
+
+        approve_action(
+            &env,
+            &manager,
+            &contract.address,
+            &contract.get_withdrawal_payload(&token1_address, &2000),
+        );
+        contract.admin_withdraw_funds(&token1_address, &2000, &None);
+
+        let final_admin_balance = check_balance(&env, &token1_address, &admin);
+        assert_eq!(
+            final_admin_balance,
+            initial_admin_balance + 2000 - calculate_fee(2000)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #13)")]
+    fn test_admin_withdraw_without_approval() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        setup_gated_pool(&env, &contract, &token1_address);
+        contract.admin_withdraw_funds(&token1_address, &2000, &None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #13)")]
+    fn test_admin_withdraw_different_amount() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let manager = setup_gated_pool(&env, &contract, &token1_address);
+        approve_action(
+            &env,
+            &manager,
+            &contract.address,
+            &contract.get_withdrawal_payload(&token1_address, &1000),
+        );
+        contract.admin_withdraw_funds(&token1_address, &2000, &None);
+    }
+}
+
+mod test_payout_operator {
+    use super::*;
+    use soroban_sdk::testutils::Ledger;
+    use soroban_sdk::{vec, IntoVal, Symbol};
+
+    // Fund the pool with 5000 of the token and register an operator
+    // allowed to pay out 2000 per day
+    fn setup_operator(
+        env: &Env,
+        contract: &LiquidityPoolClient,
+        token_address: &Address,
+    ) -> Address {
+        let depositor = Address::generate(env);
+        let token_admin = TokenAdmin::new(env, token_address);
+        mint_tokens(&token_admin, &depositor, &10000);
+        contract.add_liquidity(token_address, &5000, &depositor, &None);
+
+        let operator = Address::generate(env);
+        contract.set_payout_operator(&operator, token_address, &2000, &86400);
+        operator
+    }
+
+    #[test]
+    fn test_operator_payout() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let operator = setup_operator(&env, &contract, &token1_address);
+        let recipient = Address::generate(&env);
+
+        contract.operator_payout(&operator, &token1_address, &recipient, &1500, &None);
+
+        // Operator payouts are not charged the withdrawal fee
+        assert_eq!(check_balance(&env, &token1_address, &recipient), 1500);
+        assert_eq!(
+            check_balance(&env, &token1_address, &contract.address),
+            3500
+        );
+        assert_eq!(
+            contract
+                .get_payout_operator(&operator, &token1_address)
+                .spent,
+            1500
+        );
+        assert_eq!(
+            contract.get_available_payout(&operator, &token1_address),
+            500
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #14)")]
+    fn test_operator_payout_over_limit() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let operator = setup_operator(&env, &contract, &token1_address);
+
+        contract.operator_payout(
+            &operator,
+            &token1_address,
+            &Address::generate(&env),
+            &1500,
+            &None,
+        );
+        contract.operator_payout(
+            &operator,
+            &token1_address,
+            &Address::generate(&env),
+            &1000,
+            &None,
+        );
+    }
+
+    #[test]
+    fn test_operator_limit_resets_each_period() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let operator = setup_operator(&env, &contract, &token1_address);
+
+        contract.operator_payout(
+            &operator,
+            &token1_address,
+            &Address::generate(&env),
+            &2000,
+            &None,
+        );
+        env.ledger().with_mut(|li| {
+            li.timestamp += 86400;
+        });
+        contract.operator_payout(
+            &operator,
+            &token1_address,
+            &Address::generate(&env),
+            &2000,
+            &None,
+        );
+
+        assert_eq!(
+            check_balance(&env, &token1_address, &contract.address),
+            1000
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #9)")]
+    fn test_operator_payout_respects_min_threshold() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let operator = setup_operator(&env, &contract, &token1_address);
+        contract.set_thresholds(&token1_address, &4000, &4500);
+
+        assert_eq!(
+            contract.get_available_payout(&operator, &token1_address),
+            1000
+        );
+        contract.operator_payout(
+            &operator,
+            &token1_address,
+            &Address::generate(&env),
+            &1500,
+            &None,
+        );
+    }
+
+    #[test]
+    fn test_operator_payout_emits_alert() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let operator = setup_operator(&env, &contract, &token1_address);
+        contract.set_thresholds(&token1_address, &1000, &4000);
+
+        // Still above the alert threshold: token transfer and payout events only
+        contract.operator_payout(
+            &operator,
+            &token1_address,
+            &Address::generate(&env),
+            &500,
+            &None,
+        );
+        assert_eq!(env.events().all().len(), 2);
+
+        // Crossing the alert threshold adds an alert
+        contract.operator_payout(
+            &operator,
+            &token1_address,
+            &Address::generate(&env),
+            &1000,
+            &None,
+        );
+        let events = env.events().all();
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events.slice(2..),
+            vec![
+                &env,
+                (
+                    contract.address.clone(),
+                    (Symbol::new(&env, "alert"), token1_address.clone()).into_val(&env),
+                    (AlertType::LowLiquidity, 3500i128).into_val(&env),
+                )
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_removed_operator_cannot_pay_out() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let operator = setup_operator(&env, &contract, &token1_address);
+
+        contract.remove_payout_operator(&operator, &token1_address);
+        contract.operator_payout(
+            &operator,
+            &token1_address,
+            &Address::generate(&env),
+            &100,
+            &None,
+        );
+    }
+}
+
+mod test_idempotency {
+    use super::*;
+    use crate::constants::IDEMPOTENCY_TTL_LEDGERS;
+    use soroban_sdk::testutils::Ledger;
+    use soroban_sdk::BytesN;
+
+    // Register a depositor holding 10000 of the token
+    fn setup_depositor(env: &Env, token_address: &Address) -> Address {
+        let depositor = Address::generate(env);
+        let token_admin = TokenAdmin::new(env, token_address);
+        mint_tokens(&token_admin, &depositor, &10000);
+        depositor
+    }
+
+    #[test]
+    fn test_repeated_deposit_is_applied_once() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let depositor = setup_depositor(&env, &token1_address);
+        let key = Some(BytesN::from_array(&env, &[1; 32]));
+
+        contract.add_liquidity(&token1_address, &5000, &depositor, &key);
+        contract.add_liquidity(&token1_address, &5000, &depositor, &key);
+
+        assert_eq!(
+            contract.get_depositor_balance_amount(&token1_address, &depositor),
+            5000
+        );
+        assert_eq!(check_balance(&env, &token1_address, &depositor), 5000);
+    }
+
+    #[test]
+    fn test_repeated_withdrawal_is_applied_once() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let depositor = setup_depositor(&env, &token1_address);
+        let key = Some(BytesN::from_array(&env, &[2; 32]));
+
+        contract.add_liquidity(&token1_address, &5000, &depositor, &None);
+        contract.withdraw_deposited_tokens(&token1_address, &1000, &depositor, &key);
+        contract.withdraw_deposited_tokens(&token1_address, &1000, &depositor, &key);
+
+        assert_eq!(
+            contract.get_depositor_balance_amount(&token1_address, &depositor),
+            4000
+        );
+    }
+
+    #[test]
+    fn test_same_key_from_another_depositor_runs() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let first = setup_depositor(&env, &token1_address);
+        let second = setup_depositor(&env, &token1_address);
+        let key = Some(BytesN::from_array(&env, &[3; 32]));
+
+        contract.add_liquidity(&token1_address, &5000, &first, &key);
+        contract.add_liquidity(&token1_address, &5000, &second, &key);
+
+        assert_eq!(
+            check_balance(&env, &token1_address, &contract.address),
+            10000
+        );
+    }
+
+    #[test]
+    fn test_key_expires_after_ttl() {
+        let env = Env::default();
+        // Keep the pool and token entries alive past the key's TTL
+        env.ledger().with_mut(|li| {
+            li.min_persistent_entry_ttl = 50_000;
+        });
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let depositor = setup_depositor(&env, &token1_address);
+        let key = Some(BytesN::from_array(&env, &[4; 32]));
+
+        contract.add_liquidity(&token1_address, &2000, &depositor, &key);
+        env.ledger().with_mut(|li| {
+            li.sequence_number += IDEMPOTENCY_TTL_LEDGERS + 1;
+        });
+        contract.add_liquidity(&token1_address, &2000, &depositor, &key);
+
+        assert_eq!(
+            contract.get_depositor_balance_amount(&token1_address, &depositor),
+            4000
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use soroban_sdk::{contracterror, contracttype, Address, String};
+use soroban_sdk::{contracterror, contracttype, Address, BytesN, String, Symbol};
 
 // Data Structures
 #[contracttype]
@@ -30,6 +30,16 @@ pub struct DepositorBalance {
     pub timestamp: u64,
 }
 
+// Spending allowance of a payout operator for one asset
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PayoutOperator {
+    pub limit: i128,       // Maximum paid out per period
+    pub period: u64,       // Period length in seconds
+    pub period_start: u64, // Start of the current period
+    pub spent: i128,       // Paid out in the current period
+}
+
 #[contracterror]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Error {
@@ -45,17 +55,23 @@ pub enum Error {
     InvalidAsset = 10,
     NoDepositorBalance = 11,
     DeploymentFailed = 12,
+    ApprovalRequired = 13,
+    PayoutLimitExceeded = 14,
+    InvalidAmount = 15,
 }
 
 // Helper enum for storage keys - Pool Contract
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DataKey {
-    Admin,                              // Admin address
-    FeeCollector,                       // Fee collector address
-    Assets,                             // List of all supported assets
-    AllocationPercentage,               // Allocation percentage for this pool
-    Thresholds(Address),                // Thresholds for a specific asset
-    FeeBalance(Address),                // Balance of collected fees per asset
-    DepositorBalance(Address, Address), // Balance of a depositor (token, depositor)
+    Admin,                                  // Admin address
+    FeeCollector,                           // Fee collector address
+    Assets,                                 // List of all supported assets
+    AllocationPercentage,                   // Allocation percentage for this pool
+    Thresholds(Address),                    // Thresholds for a specific asset
+    FeeBalance(Address),                    // Balance of collected fees per asset
+    DepositorBalance(Address, Address),     // Balance of a depositor (token, depositor)
+    ApprovalManager,                        // Optional approval manager gating admin withdrawals
+    PayoutOperator(Address, Address),       // Payout allowance of an operator (operator, token)
+    Processed(Symbol, Address, BytesN<32>), // Processed idempotency key (function, caller, key)
 }
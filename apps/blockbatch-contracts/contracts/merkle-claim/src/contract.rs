@@ -0,0 +1,193 @@
+use crate::merkle;
+use crate::types::{ClaimError, DataKey, Distribution, DistributionStatus};
+use soroban_sdk::{contract, contractimpl, symbol_short, token::Client, Address, BytesN, Env, Vec};
+
+// Number of claim flags packed into one bitmap word
+const WORD_BITS: u32 = 128;
+
+#[contract]
+pub struct MerkleClaim;
+
+#[contractimpl]
+impl MerkleClaim {
+    // Publish a Merkle root over the entitlements and fund it with the full
+    // amount in one transfer from the operator
+    pub fn create_distribution(
+        env: Env,
+        operator: Address,
+        token: Address,
+        root: BytesN<32>,
+        total_amount: i128,
+        deadline: u64,
+    ) -> Result<u64, ClaimError> {
+        if total_amount <= 0 {
+            return Err(ClaimError::InvalidAmount);
+        }
+        if deadline <= env.ledger().timestamp() {
+            return Err(ClaimError::InvalidDeadline);
+        }
+
+        operator.require_auth();
+
+        let distribution_id = Self::get_distribution_count(&env) + 1;
+        let distribution = Distribution {
+            id: distribution_id,
+            operator: operator.clone(),
+            token: token.clone(),
+            root,
+            total_amount,
+            claimed_amount: 0,
+            deadline,
+            status: DistributionStatus::Active,
+            created_at: env.ledger().timestamp(),
+        };
+
+        Client::new(&env, &token).transfer(
+            &operator,
+            &env.current_contract_address(),
+            &total_amount,
+        );
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Distribution(distribution_id), &distribution);
+        env.storage()
+            .persistent()
+            .set(&DataKey::DistributionCount, &distribution_id);
+
+        env.events().publish(
+            (symbol_short!("created"), distribution_id, operator),
+            (token, total_amount, deadline),
+        );
+
+        Ok(distribution_id)
+    }
+
+    // Pay out one leaf. Anyone may submit the claim; the funds always go to
+    // the recipient committed to in the leaf.
+    pub fn claim(
+        env: Env,
+        distribution_id: u64,
+        index: u32,
+        recipient: Address,
+        amount: i128,
+        proof: Vec<BytesN<32>>,
+    ) -> Result<(), ClaimError> {
+        let mut distribution = Self::get_distribution_by_id(&env, distribution_id)?;
+
+        if distribution.status != DistributionStatus::Active {
+            return Err(ClaimError::InvalidStatus);
+        }
+        if env.ledger().timestamp() >= distribution.deadline {
+            return Err(ClaimError::ClaimPeriodEnded);
+        }
+        if Self::is_claimed_index(&env, distribution_id, index) {
+            return Err(ClaimError::AlreadyClaimed);
+        }
+
+        let leaf = merkle::leaf_hash(&env, index, &recipient, &distribution.token, amount);
+        if !merkle::verify(&env, &proof, &distribution.root, leaf) {
+            return Err(ClaimError::InvalidProof);
+        }
+        // A root that commits to more than was funded cannot overdraw other distributions
+        if distribution.claimed_amount + amount > distribution.total_amount {
+            return Err(ClaimError::InsufficientFunds);
+        }
+
+        Self::set_claimed(&env, distribution_id, index);
+        distribution.claimed_amount += amount;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Distribution(distribution_id), &distribution);
+
+        Client::new(&env, &distribution.token).transfer(
+            &env.current_contract_address(),
+            &recipient,
+            &amount,
+        );
+
+        env.events().publish(
+            (symbol_short!("claimed"), distribution_id, recipient),
+            (index, amount),
+        );
+
+        Ok(())
+    }
+
+    // Return everything that was not claimed by the deadline to the operator
+    pub fn sweep(env: Env, distribution_id: u64) -> Result<i128, ClaimError> {
+        let mut distribution = Self::get_distribution_by_id(&env, distribution_id)?;
+
+        if distribution.status != DistributionStatus::Active {
+            return Err(ClaimError::InvalidStatus);
+        }
+        if env.ledger().timestamp() < distribution.deadline {
+            return Err(ClaimError::ClaimPeriodActive);
+        }
+
+        distribution.operator.require_auth();
+
+        let unclaimed = distribution.total_amount - distribution.claimed_amount;
+        distribution.status = DistributionStatus::Swept;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Distribution(distribution_id), &distribution);
+
+        if unclaimed > 0 {
+            Client::new(&env, &distribution.token).transfer(
+                &env.current_contract_address(),
+                &distribution.operator,
+                &unclaimed,
+            );
+        }
+
+        env.events()
+            .publish((symbol_short!("swept"), distribution_id), unclaimed);
+
+        Ok(unclaimed)
+    }
+
+    pub fn is_claimed(env: Env, distribution_id: u64, index: u32) -> bool {
+        Self::is_claimed_index(&env, distribution_id, index)
+    }
+
+    pub fn get_distribution(env: Env, distribution_id: u64) -> Result<Distribution, ClaimError> {
+        Self::get_distribution_by_id(&env, distribution_id)
+    }
+
+    // Helper functions
+    fn is_claimed_index(env: &Env, distribution_id: u64, index: u32) -> bool {
+        let word = Self::get_claimed_word(env, distribution_id, index / WORD_BITS);
+        word & (1u128 << (index % WORD_BITS)) != 0
+    }
+
+    fn set_claimed(env: &Env, distribution_id: u64, index: u32) {
+        let word_index = index / WORD_BITS;
+        let word = Self::get_claimed_word(env, distribution_id, word_index);
+        env.storage().persistent().set(
+            &DataKey::Claimed(distribution_id, word_index),
+            &(word | (1u128 << (index % WORD_BITS))),
+        );
+    }
+
+    fn get_claimed_word(env: &Env, distribution_id: u64, word_index: u32) -> u128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Claimed(distribution_id, word_index))
+            .unwrap_or(0)
+    }
+
+    fn get_distribution_count(env: &Env) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::DistributionCount)
+            .unwrap_or(0)
+    }
+
+    fn get_distribution_by_id(env: &Env, distribution_id: u64) -> Result<Distribution, ClaimError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Distribution(distribution_id))
+            .ok_or(ClaimError::DistributionNotFound)
+    }
+}
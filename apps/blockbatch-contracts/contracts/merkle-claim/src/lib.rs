@@ -0,0 +1,13 @@
+#![no_std]
+
+mod contract;
+mod merkle;
+mod types;
+
+pub use contract::*;
+
+#[cfg(test)]
+mod test;
+
+#[cfg(test)]
+mod testutils;
@@ -0,0 +1,36 @@
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{Address, Bytes, BytesN, Env, Vec};
+
+// Leaf hash of one entitlement: sha256 over the XDR of the tuple
+pub(crate) fn leaf_hash(
+    env: &Env,
+    index: u32,
+    recipient: &Address,
+    token: &Address,
+    amount: i128,
+) -> BytesN<32> {
+    let leaf = (index, recipient.clone(), token.clone(), amount);
+    env.crypto().sha256(&leaf.to_xdr(env)).to_bytes()
+}
+
+// Parent hash of two nodes. The pair is sorted first, so proofs do not need
+// to say on which side each sibling sits.
+pub(crate) fn hash_pair(env: &Env, a: &BytesN<32>, b: &BytesN<32>) -> BytesN<32> {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut data = Bytes::from(first.clone());
+    data.append(&Bytes::from(second.clone()));
+    env.crypto().sha256(&data).to_bytes()
+}
+
+pub(crate) fn verify(
+    env: &Env,
+    proof: &Vec<BytesN<32>>,
+    root: &BytesN<32>,
+    leaf: BytesN<32>,
+) -> bool {
+    let mut node = leaf;
+    for sibling in proof.iter() {
+        node = hash_pair(env, &node, &sibling);
+    }
+    node == *root
+}
@@ -0,0 +1,248 @@
+use super::*;
+use crate::testutils::{
+    build_tree, check_balance, create_token_contract, mint_tokens, Entitlement,
+};
+use crate::types::*;
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::{Address, BytesN, Env, Vec};
+
+#[cfg(test)]
+mod test_setup {
+    use super::*;
+
+    pub const DEADLINE: u64 = 10_000;
+
+    pub struct Setup<'a> {
+        pub client: MerkleClaimClient<'a>,
+        pub operator: Address,
+        pub token: Address,
+        pub entitlements: [Entitlement; 4],
+        pub proofs: [Vec<BytesN<32>>; 4],
+        pub distribution_id: u64,
+    }
+
+    // A distribution of 1000 + 2000 + 3000 + 4000, with indexes spread over
+    // two bitmap words
+    pub fn setup_contract(e: &Env) -> Setup<'_> {
+        let admin = Address::generate(e);
+        let operator = Address::generate(e);
+
+        let (token, token_admin) = create_token_contract(e, &admin);
+
+        let contract_id = e.register(MerkleClaim, ());
+        let client = MerkleClaimClient::new(e, &contract_id);
+
+        e.mock_all_auths();
+
+        mint_tokens(&token_admin, &operator, &20_000);
+
+        let entitlements = [
+            Entitlement {
+                index: 0,
+                recipient: Address::generate(e),
+                amount: 1_000,
+            },
+            Entitlement {
+                index: 1,
+                recipient: Address::generate(e),
+                amount: 2_000,
+            },
+            Entitlement {
+                index: 130,
+                recipient: Address::generate(e),
+                amount: 3_000,
+            },
+            Entitlement {
+                index: 255,
+                recipient: Address::generate(e),
+                amount: 4_000,
+            },
+        ];
+        let (root, proofs) = build_tree(e, &token, &entitlements);
+        let distribution_id =
+            client.create_distribution(&operator, &token, &root, &10_000, &DEADLINE);
+
+        Setup {
+            client,
+            operator,
+            token,
+            entitlements,
+            proofs,
+            distribution_id,
+        }
+    }
+
+    pub fn claim(s: &Setup, leaf: usize) {
+        let entitlement = &s.entitlements[leaf];
+        s.client.claim(
+            &s.distribution_id,
+            &entitlement.index,
+            &entitlement.recipient,
+            &entitlement.amount,
+            &s.proofs[leaf],
+        );
+    }
+}
+
+mod test_create {
+    use super::*;
+
+    #[test]
+    fn test_create_distribution_funds_contract() {
+        let env = Env::default();
+        let s = test_setup::setup_contract(&env);
+
+        let distribution = s.client.get_distribution(&s.distribution_id);
+        assert_eq!(distribution.total_amount, 10_000);
+        assert_eq!(distribution.claimed_amount, 0);
+        assert_eq!(distribution.status, DistributionStatus::Active);
+        assert_eq!(check_balance(&env, &s.token, &s.client.address), 10_000);
+        assert_eq!(check_balance(&env, &s.token, &s.operator), 10_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #9)")]
+    fn test_create_distribution_past_deadline() {
+        let env = Env::default();
+        let s = test_setup::setup_contract(&env);
+        env.ledger().with_mut(|li| {
+            li.timestamp = 500;
+        });
+        let root = s.client.get_distribution(&s.distribution_id).root;
+        s.client
+            .create_distribution(&s.operator, &s.token, &root, &1_000, &500);
+    }
+}
+
+mod test_claim {
+    use super::*;
+
+    #[test]
+    fn test_claim_all_leaves() {
+        let env = Env::default();
+        let s = test_setup::setup_contract(&env);
+
+        for leaf in 0..4 {
+            test_setup::claim(&s, leaf);
+            let entitlement = &s.entitlements[leaf];
+            assert_eq!(
+                check_balance(&env, &s.token, &entitlement.recipient),
+                entitlement.amount
+            );
+            assert!(s.client.is_claimed(&s.distribution_id, &entitlement.index));
+        }
+
+        assert_eq!(
+            s.client.get_distribution(&s.distribution_id).claimed_amount,
+            10_000
+        );
+        assert_eq!(check_balance(&env, &s.token, &s.client.address), 0);
+    }
+
+    #[test]
+    fn test_claim_sets_only_its_bit() {
+        let env = Env::default();
+        let s = test_setup::setup_contract(&env);
+
+        test_setup::claim(&s, 2);
+
+        assert!(s.client.is_claimed(&s.distribution_id, &130));
+        assert!(!s.client.is_claimed(&s.distribution_id, &2));
+        assert!(!s.client.is_claimed(&s.distribution_id, &131));
+        assert!(!s.client.is_claimed(&s.distribution_id, &255));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #4)")]
+    fn test_claim_twice() {
+        let env = Env::default();
+        let s = test_setup::setup_contract(&env);
+        test_setup::claim(&s, 1);
+        test_setup::claim(&s, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #5)")]
+    fn test_claim_wrong_amount() {
+        let env = Env::default();
+        let s = test_setup::setup_contract(&env);
+        let entitlement = &s.entitlements[0];
+        s.client.claim(
+            &s.distribution_id,
+            &entitlement.index,
+            &entitlement.recipient,
+            &(entitlement.amount * 2),
+            &s.proofs[0],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #5)")]
+    fn test_claim_other_recipient() {
+        let env = Env::default();
+        let s = test_setup::setup_contract(&env);
+        let entitlement = &s.entitlements[0];
+        s.client.claim(
+            &s.distribution_id,
+            &entitlement.index,
+            &Address::generate(&env),
+            &entitlement.amount,
+            &s.proofs[0],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #6)")]
+    fn test_claim_after_deadline() {
+        let env = Env::default();
+        let s = test_setup::setup_contract(&env);
+        env.ledger().with_mut(|li| {
+            li.timestamp = test_setup::DEADLINE;
+        });
+        test_setup::claim(&s, 0);
+    }
+}
+
+mod test_sweep {
+    use super::*;
+
+    #[test]
+    fn test_sweep_unclaimed_after_deadline() {
+        let env = Env::default();
+        let s = test_setup::setup_contract(&env);
+        test_setup::claim(&s, 0);
+        test_setup::claim(&s, 3);
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = test_setup::DEADLINE;
+        });
+        assert_eq!(s.client.sweep(&s.distribution_id), 5_000);
+
+        assert_eq!(check_balance(&env, &s.token, &s.operator), 15_000);
+        assert_eq!(check_balance(&env, &s.token, &s.client.address), 0);
+        assert_eq!(
+            s.client.get_distribution(&s.distribution_id).status,
+            DistributionStatus::Swept
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_sweep_before_deadline() {
+        let env = Env::default();
+        let s = test_setup::setup_contract(&env);
+        s.client.sweep(&s.distribution_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #8)")]
+    fn test_sweep_twice() {
+        let env = Env::default();
+        let s = test_setup::setup_contract(&env);
+        env.ledger().with_mut(|li| {
+            li.timestamp = test_setup::DEADLINE;
+        });
+        s.client.sweep(&s.distribution_id);
+        s.client.sweep(&s.distribution_id);
+    }
+}
@@ -0,0 +1,66 @@
+#![cfg(test)]
+extern crate std;
+
+use crate::merkle::{hash_pair, leaf_hash};
+use soroban_sdk::{
+    token::{StellarAssetClient as TokenAdmin, TokenClient},
+    vec, Address, BytesN, Env, Vec,
+};
+
+// One entitlement of a test distribution
+pub(crate) struct Entitlement {
+    pub index: u32,
+    pub recipient: Address,
+    pub amount: i128,
+}
+
+// Helper to create a token contract
+pub(crate) fn create_token_contract<'a>(e: &Env, admin: &Address) -> (Address, TokenAdmin<'a>) {
+    let token_contract_id = e.register_stellar_asset_contract_v2(admin.clone());
+    let token = TokenAdmin::new(e, &token_contract_id.address());
+    (token_contract_id.address(), token)
+}
+
+// Helper to mint tokens to an address
+pub(crate) fn mint_tokens(token: &TokenAdmin, to: &Address, amount: &i128) {
+    token.mint(to, amount);
+}
+
+// Helper to check token balance
+pub(crate) fn check_balance(e: &Env, token_address: &Address, account: &Address) -> i128 {
+    let token_client = TokenClient::new(e, token_address);
+    token_client.balance(account)
+}
+
+// Helper to build a four-leaf tree. Returns the root and the proof of every leaf.
+pub(crate) fn build_tree(
+    e: &Env,
+    token: &Address,
+    entitlements: &[Entitlement; 4],
+) -> (BytesN<32>, [Vec<BytesN<32>>; 4]) {
+    let leaves: std::vec::Vec<BytesN<32>> = entitlements
+        .iter()
+        .map(|entitlement| {
+            leaf_hash(
+                e,
+                entitlement.index,
+                &entitlement.recipient,
+                token,
+                entitlement.amount,
+            )
+        })
+        .collect();
+
+    let left = hash_pair(e, &leaves[0], &leaves[1]);
+    let right = hash_pair(e, &leaves[2], &leaves[3]);
+    let root = hash_pair(e, &left, &right);
+
+    let proofs = [
+        vec![e, leaves[1].clone(), right.clone()],
+        vec![e, leaves[0].clone(), right.clone()],
+        vec![e, leaves[3].clone(), left.clone()],
+        vec![e, leaves[2].clone(), left.clone()],
+    ];
+
+    (root, proofs)
+}
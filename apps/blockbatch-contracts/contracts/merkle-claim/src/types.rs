@@ -0,0 +1,47 @@
+use soroban_sdk::{contracterror, contracttype, Address, BytesN};
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DistributionStatus {
+    Active,
+    Swept,
+}
+
+// A funded Merkle distribution. Each leaf commits to one
+// (index, recipient, token, amount) entitlement.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Distribution {
+    pub id: u64,
+    pub operator: Address,
+    pub token: Address,
+    pub root: BytesN<32>,
+    pub total_amount: i128,
+    pub claimed_amount: i128,
+    pub deadline: u64, // Claims close and unclaimed funds can be swept from here on
+    pub status: DistributionStatus,
+    pub created_at: u64,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ClaimError {
+    Unauthorized = 1,
+    InvalidAmount = 2,
+    DistributionNotFound = 3,
+    AlreadyClaimed = 4,
+    InvalidProof = 5,
+    ClaimPeriodEnded = 6,
+    ClaimPeriodActive = 7,
+    InvalidStatus = 8,
+    InvalidDeadline = 9,
+    InsufficientFunds = 10,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    DistributionCount, // Number of distributions created so far
+    Distribution(u64), // Distribution details by ID
+    Claimed(u64, u32), // Claimed-bitmap word by (distribution ID, index / 128)
+}
@@ -0,0 +1,179 @@
+use crate::types::{DataKey, Stream, StreamError, StreamStatus};
+use soroban_sdk::{contract, contractimpl, symbol_short, token::Client, Address, Env};
+
+#[contract]
+pub struct PaymentStream;
+
+#[contractimpl]
+impl PaymentStream {
+    // Open a stream funded with `deposit`, which must equal the rate times
+    // the stream's duration
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_stream(
+        env: Env,
+        employer: Address,
+        recipient: Address,
+        token: Address,
+        rate_per_second: i128,
+        start_time: u64,
+        stop_time: u64,
+        deposit: i128,
+    ) -> Result<u64, StreamError> {
+        if rate_per_second <= 0 {
+            return Err(StreamError::InvalidAmount);
+        }
+        if start_time >= stop_time || stop_time <= env.ledger().timestamp() {
+            return Err(StreamError::InvalidTime);
+        }
+        if deposit != rate_per_second * (stop_time - start_time) as i128 {
+            return Err(StreamError::InvalidDeposit);
+        }
+
+        employer.require_auth();
+
+        let stream_id = Self::get_stream_count(&env) + 1;
+        let stream = Stream {
+            id: stream_id,
+            employer: employer.clone(),
+            recipient: recipient.clone(),
+            token: token.clone(),
+            rate_per_second,
+            start_time,
+            stop_time,
+            deposit,
+            withdrawn: 0,
+            status: StreamStatus::Active,
+            created_at: env.ledger().timestamp(),
+        };
+
+        Client::new(&env, &token).transfer(&employer, &env.current_contract_address(), &deposit);
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Stream(stream_id), &stream);
+        env.storage()
+            .persistent()
+            .set(&DataKey::StreamCount, &stream_id);
+
+        env.events().publish(
+            (symbol_short!("created"), stream_id, employer, recipient),
+            (token, rate_per_second, start_time, stop_time),
+        );
+
+        Ok(stream_id)
+    }
+
+    // Pay the recipient everything that has accrued and not been withdrawn yet
+    pub fn withdraw(env: Env, stream_id: u64) -> Result<i128, StreamError> {
+        let mut stream = Self::get_stream_by_id(&env, stream_id)?;
+
+        if stream.status != StreamStatus::Active {
+            return Err(StreamError::InvalidStatus);
+        }
+
+        stream.recipient.require_auth();
+
+        let amount = Self::accrued(&env, &stream) - stream.withdrawn;
+        if amount <= 0 {
+            return Err(StreamError::NothingToWithdraw);
+        }
+
+        stream.withdrawn += amount;
+        if stream.withdrawn == stream.deposit {
+            stream.status = StreamStatus::Completed;
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::Stream(stream_id), &stream);
+
+        Client::new(&env, &stream.token).transfer(
+            &env.current_contract_address(),
+            &stream.recipient,
+            &amount,
+        );
+
+        env.events().publish(
+            (symbol_short!("withdrawn"), stream_id, stream.recipient),
+            amount,
+        );
+
+        Ok(amount)
+    }
+
+    // Stop the stream: the recipient is paid what has accrued so far and the
+    // employer gets back the part of the deposit that has not
+    pub fn cancel_stream(env: Env, stream_id: u64) -> Result<(), StreamError> {
+        let mut stream = Self::get_stream_by_id(&env, stream_id)?;
+
+        if stream.status != StreamStatus::Active {
+            return Err(StreamError::InvalidStatus);
+        }
+
+        stream.employer.require_auth();
+
+        let accrued = Self::accrued(&env, &stream);
+        let recipient_amount = accrued - stream.withdrawn;
+        let refund = stream.deposit - accrued;
+
+        stream.withdrawn = accrued;
+        stream.status = StreamStatus::Cancelled;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Stream(stream_id), &stream);
+
+        let token_client = Client::new(&env, &stream.token);
+        if recipient_amount > 0 {
+            token_client.transfer(
+                &env.current_contract_address(),
+                &stream.recipient,
+                &recipient_amount,
+            );
+        }
+        if refund > 0 {
+            token_client.transfer(&env.current_contract_address(), &stream.employer, &refund);
+        }
+
+        env.events().publish(
+            (symbol_short!("cancelled"), stream_id),
+            (recipient_amount, refund),
+        );
+
+        Ok(())
+    }
+
+    // Amount the recipient could withdraw right now
+    pub fn withdrawable(env: Env, stream_id: u64) -> Result<i128, StreamError> {
+        let stream = Self::get_stream_by_id(&env, stream_id)?;
+        if stream.status != StreamStatus::Active {
+            return Ok(0);
+        }
+        Ok(Self::accrued(&env, &stream) - stream.withdrawn)
+    }
+
+    pub fn get_stream(env: Env, stream_id: u64) -> Result<Stream, StreamError> {
+        Self::get_stream_by_id(&env, stream_id)
+    }
+
+    // Helper functions
+    fn accrued(env: &Env, stream: &Stream) -> i128 {
+        let now = env.ledger().timestamp().min(stream.stop_time);
+        if now <= stream.start_time {
+            return 0;
+        }
+        stream.rate_per_second * (now - stream.start_time) as i128
+    }
+
+    fn get_stream_count(env: &Env) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::StreamCount)
+            .unwrap_or(0)
+    }
+
+    fn get_stream_by_id(env: &Env, stream_id: u64) -> Result<Stream, StreamError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Stream(stream_id))
+            .ok_or(StreamError::StreamNotFound)
+    }
+}
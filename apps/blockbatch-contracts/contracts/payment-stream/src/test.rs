@@ -0,0 +1,171 @@
+use super::*;
+use crate::testutils::{check_balance, create_token_contract, mint_tokens};
+use crate::types::*;
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::{Address, Env};
+
+#[cfg(test)]
+mod test_setup {
+    use super::*;
+
+    pub const START: u64 = 1_000;
+    pub const STOP: u64 = 2_000;
+    pub const RATE: i128 = 5;
+
+    // A stream of 5 per second over 1000 seconds, funded with 5000
+    pub fn setup_contract(e: &Env) -> (PaymentStreamClient<'_>, Address, Address, Address, u64) {
+        let admin = Address::generate(e);
+        let employer = Address::generate(e);
+        let recipient = Address::generate(e);
+
+        let (token, token_admin) = create_token_contract(e, &admin);
+
+        let contract_id = e.register(PaymentStream, ());
+        let client = PaymentStreamClient::new(e, &contract_id);
+
+        e.mock_all_auths();
+
+        mint_tokens(&token_admin, &employer, &10_000);
+
+        let stream_id = client.create_stream(
+            &employer,
+            &recipient,
+            &token,
+            &RATE,
+            &START,
+            &STOP,
+            &(RATE * (STOP - START) as i128),
+        );
+
+        (client, employer, recipient, token, stream_id)
+    }
+
+    pub fn set_time(e: &Env, timestamp: u64) {
+        e.ledger().with_mut(|li| {
+            li.timestamp = timestamp;
+        });
+    }
+}
+
+mod test_create {
+    use super::*;
+
+    #[test]
+    fn test_create_stream() {
+        let env = Env::default();
+        let (client, employer, _, token, stream_id) = test_setup::setup_contract(&env);
+
+        let stream = client.get_stream(&stream_id);
+        assert_eq!(stream.deposit, 5_000);
+        assert_eq!(stream.status, StreamStatus::Active);
+        assert_eq!(check_balance(&env, &token, &employer), 5_000);
+        assert_eq!(check_balance(&env, &token, &client.address), 5_000);
+        assert_eq!(client.withdrawable(&stream_id), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #4)")]
+    fn test_create_stream_wrong_deposit() {
+        let env = Env::default();
+        let (client, employer, recipient, token, _) = test_setup::setup_contract(&env);
+        client.create_stream(&employer, &recipient, &token, &5, &1_000, &2_000, &4_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_create_stream_stop_before_start() {
+        let env = Env::default();
+        let (client, employer, recipient, token, _) = test_setup::setup_contract(&env);
+        client.create_stream(&employer, &recipient, &token, &5, &2_000, &1_000, &5_000);
+    }
+}
+
+mod test_withdraw {
+    use super::*;
+
+    #[test]
+    fn test_withdraw_accrued() {
+        let env = Env::default();
+        let (client, _, recipient, token, stream_id) = test_setup::setup_contract(&env);
+
+        test_setup::set_time(&env, test_setup::START + 100);
+        assert_eq!(client.withdrawable(&stream_id), 500);
+        assert_eq!(client.withdraw(&stream_id), 500);
+
+        test_setup::set_time(&env, test_setup::START + 250);
+        assert_eq!(client.withdraw(&stream_id), 750);
+
+        assert_eq!(check_balance(&env, &token, &recipient), 1_250);
+        assert_eq!(client.get_stream(&stream_id).withdrawn, 1_250);
+    }
+
+    #[test]
+    fn test_withdraw_after_stop_completes_stream() {
+        let env = Env::default();
+        let (client, _, recipient, token, stream_id) = test_setup::setup_contract(&env);
+
+        test_setup::set_time(&env, test_setup::STOP + 500);
+        assert_eq!(client.withdraw(&stream_id), 5_000);
+
+        assert_eq!(check_balance(&env, &token, &recipient), 5_000);
+        assert_eq!(
+            client.get_stream(&stream_id).status,
+            StreamStatus::Completed
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_withdraw_before_start() {
+        let env = Env::default();
+        let (client, _, _, _, stream_id) = test_setup::setup_contract(&env);
+        test_setup::set_time(&env, test_setup::START);
+        client.withdraw(&stream_id);
+    }
+}
+
+mod test_cancel {
+    use super::*;
+
+    #[test]
+    fn test_cancel_settles_and_refunds() {
+        let env = Env::default();
+        let (client, employer, recipient, token, stream_id) = test_setup::setup_contract(&env);
+
+        test_setup::set_time(&env, test_setup::START + 100);
+        client.withdraw(&stream_id);
+        test_setup::set_time(&env, test_setup::START + 400);
+        client.cancel_stream(&stream_id);
+
+        // 2000 accrued in total, 500 of which was already withdrawn
+        assert_eq!(check_balance(&env, &token, &recipient), 2_000);
+        assert_eq!(check_balance(&env, &token, &employer), 8_000);
+        assert_eq!(check_balance(&env, &token, &client.address), 0);
+
+        let stream = client.get_stream(&stream_id);
+        assert_eq!(stream.status, StreamStatus::Cancelled);
+        assert_eq!(stream.withdrawn, 2_000);
+        assert_eq!(client.withdrawable(&stream_id), 0);
+    }
+
+    #[test]
+    fn test_cancel_before_start_refunds_all() {
+        let env = Env::default();
+        let (client, employer, recipient, token, stream_id) = test_setup::setup_contract(&env);
+
+        client.cancel_stream(&stream_id);
+
+        assert_eq!(check_balance(&env, &token, &recipient), 0);
+        assert_eq!(check_balance(&env, &token, &employer), 10_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #6)")]
+    fn test_withdraw_after_cancel() {
+        let env = Env::default();
+        let (client, _, _, _, stream_id) = test_setup::setup_contract(&env);
+        test_setup::set_time(&env, test_setup::START + 100);
+        client.cancel_stream(&stream_id);
+        client.withdraw(&stream_id);
+    }
+}
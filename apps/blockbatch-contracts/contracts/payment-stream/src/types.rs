@@ -0,0 +1,46 @@
+use soroban_sdk::{contracterror, contracttype, Address};
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StreamStatus {
+    Active,
+    Completed,
+    Cancelled,
+}
+
+// Salary streamed from an employer's deposit to a recipient, accruing every
+// second between start_time and stop_time
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Stream {
+    pub id: u64,
+    pub employer: Address,
+    pub recipient: Address,
+    pub token: Address,
+    pub rate_per_second: i128,
+    pub start_time: u64,
+    pub stop_time: u64,
+    pub deposit: i128,
+    pub withdrawn: i128, // Paid out to the recipient so far
+    pub status: StreamStatus,
+    pub created_at: u64,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StreamError {
+    Unauthorized = 1,
+    InvalidAmount = 2,
+    InvalidTime = 3,
+    InvalidDeposit = 4,
+    StreamNotFound = 5,
+    InvalidStatus = 6,
+    NothingToWithdraw = 7,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    StreamCount, // Number of streams created so far
+    Stream(u64), // Stream details by ID
+}
@@ -2,7 +2,7 @@
 
 mod modules;
 
-use soroban_sdk::{contract, contractimpl, Env, String, Vec};
+use soroban_sdk::{contract, contractimpl, BytesN, Env, String, Vec};
 
 use modules::types::{Asset, ComplianceInfo, RemittanceContract, RemittanceStatus, TimePoint};
 use modules::storage::RemittanceStorage;
@@ -199,12 +199,14 @@ impl RemittanceContractService {
     /// # Arguments
     ///
     /// * `contract_id` - The ID of the remittance contract
+    /// * `idempotency_key` - Optional client-supplied key; repeating a processed
+    ///   key returns the original status instead of executing again
     ///
     /// # Returns
     ///
     /// The new status of the remittance
-    pub fn execute_remittance(env: Env, contract_id: String) -> RemittanceStatus {
-        match ExecutionService::execute_remittance(&env, &contract_id) {
+    pub fn execute_remittance(env: Env, contract_id: String, idempotency_key: Option<BytesN<32>>) -> RemittanceStatus {
+        match ExecutionService::execute_remittance(&env, &contract_id, &idempotency_key) {
             Ok(status) => status,
             Err(_) => panic!("Failed to execute remittance"),
         }
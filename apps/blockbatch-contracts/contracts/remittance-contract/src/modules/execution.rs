@@ -1,4 +1,4 @@
-use soroban_sdk::Env;
+use soroban_sdk::{BytesN, Env};
 use crate::modules::types::{RemittanceContract, RemittanceStatus};
 use crate::modules::storage::{RemittanceStorage, StorageError};
 use crate::modules::compliance::ComplianceService;
@@ -30,7 +30,33 @@ pub struct ExecutionService;
 
 impl ExecutionService {
     /// Execute a remittance
+    ///
+    /// If an idempotency key is given and was already processed for this
+    /// remittance, the original status is returned without executing again.
     pub fn execute_remittance(
+        env: &Env,
+        contract_id: &soroban_sdk::String,
+        idempotency_key: &Option<BytesN<32>>
+    ) -> Result<RemittanceStatus, ExecutionError> {
+        // Return the original result for a key that was already processed
+        if let Some(key) = idempotency_key {
+            if let Some(status) = RemittanceStorage::get_processed(env, contract_id, key) {
+                return Ok(status);
+            }
+        }
+        
+        let status = Self::run_remittance(env, contract_id)?;
+        
+        // Remember the result until the key expires
+        if let Some(key) = idempotency_key {
+            RemittanceStorage::store_processed(env, contract_id, key, &status);
+        }
+        
+        Ok(status)
+    }
+    
+    /// Run the checks and the payment of a remittance
+    fn run_remittance(
         env: &Env,
         contract_id: &soroban_sdk::String
     ) -> Result<RemittanceStatus, ExecutionError> {
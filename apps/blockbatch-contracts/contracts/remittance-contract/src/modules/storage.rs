@@ -1,5 +1,8 @@
-use soroban_sdk::{Env, String, Symbol, Vec};
-use crate::modules::types::{RemittanceContract, RemittanceHistory};
+use soroban_sdk::{BytesN, Env, String, Symbol, Vec};
+use crate::modules::types::{RemittanceContract, RemittanceHistory, RemittanceStatus};
+
+/// Number of ledgers a processed idempotency key is remembered for (~1 day)
+pub const IDEMPOTENCY_TTL: u32 = 17_280;
 
 /// Error type for storage operations
 #[derive(Debug)]
@@ -79,6 +82,25 @@ impl RemittanceStorage {
         }
     }
     
+    /// Remember the result of an execution made with an idempotency key.
+    /// The entry lives in temporary storage and expires with its TTL.
+    pub fn store_processed(env: &Env, contract_id: &String, idempotency_key: &BytesN<32>, status: &RemittanceStatus) {
+        let key = Self::get_processed_key(env, contract_id, idempotency_key);
+        env.storage().temporary().set(&key, status);
+        env.storage().temporary().extend_ttl(&key, IDEMPOTENCY_TTL, IDEMPOTENCY_TTL);
+    }
+    
+    /// Get the result of an earlier execution made with the same idempotency key
+    pub fn get_processed(env: &Env, contract_id: &String, idempotency_key: &BytesN<32>) -> Option<RemittanceStatus> {
+        let key = Self::get_processed_key(env, contract_id, idempotency_key);
+        env.storage().temporary().get(&key)
+    }
+    
+    /// Get processed key storage key from contract ID and idempotency key
+    pub fn get_processed_key(env: &Env, contract_id: &String, idempotency_key: &BytesN<32>) -> (Symbol, String, BytesN<32>) {
+        (Symbol::new(env, "IDEM"), contract_id.clone(), idempotency_key.clone())
+    }
+    
     /// Get contract storage key from contract ID
     pub fn get_contract_key(_contract_id: &String) -> Symbol {
         // Use the contract ID directly as a key
@@ -1,7 +1,7 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Env, String};
+use soroban_sdk::{testutils::Address as _, Address, BytesN, Env, String};
 use soroban_sdk::testutils::Ledger;
 use modules::types::{Asset, RemittanceStatus};
 
@@ -145,7 +145,7 @@ fn test_execute_remittance() {
         let remittance = create_test_remittance(&env);
         
         // Execute the remittance
-        let status = RemittanceContractService::execute_remittance(env.clone(), remittance.id);
+        let status = RemittanceContractService::execute_remittance(env.clone(), remittance.id, None);
         
         // Check that remittance was completed
         assert!(matches!(status, RemittanceStatus::Completed));
@@ -175,7 +175,7 @@ fn test_get_remittance_status() {
         let remittance = create_test_remittance(&env);
         
         // Execute the remittance
-        RemittanceContractService::execute_remittance(env.clone(), remittance.id.clone());
+        RemittanceContractService::execute_remittance(env.clone(), remittance.id.clone(), None);
         
         // Get updated status
         let updated_status = RemittanceContractService::get_remittance_status(env.clone(), remittance.id);
@@ -244,7 +244,7 @@ fn test_get_remittance_history() {
         let remittance = create_test_remittance_with_sender(&env, &sender_account);
         
         // Execute the remittance to add to history
-        RemittanceContractService::execute_remittance(env.clone(), remittance.id.clone());
+        RemittanceContractService::execute_remittance(env.clone(), remittance.id.clone(), None);
         
         // Now the history should contain one entry
         let history = RemittanceContractService::get_remittance_history(env.clone(), sender_account);
@@ -266,13 +266,59 @@ fn test_expired_remittance() {
         env.ledger().set_timestamp(env.ledger().timestamp() + 10);
         
         // Try to execute the expired remittance
-        let status = RemittanceContractService::execute_remittance(env.clone(), remittance.id);
+        let status = RemittanceContractService::execute_remittance(env.clone(), remittance.id, None);
         
         // Check that remittance was cancelled due to expiration
         assert!(matches!(status, RemittanceStatus::Cancelled));
     });
 }
 
+#[test]
+fn test_execute_remittance_idempotent() {
+    let env = create_test_env();
+    let contract_id = create_test_contract(&env);
+    
+    env.as_contract(&contract_id, || {
+        let remittance = create_test_remittance(&env);
+        let key = Some(BytesN::from_array(&env, &[1; 32]));
+        
+        // Execute twice with the same key
+        let first = RemittanceContractService::execute_remittance(env.clone(), remittance.id.clone(), key.clone());
+        let second = RemittanceContractService::execute_remittance(env.clone(), remittance.id.clone(), key);
+        
+        // The retry returns the original result instead of failing on a completed remittance
+        assert!(matches!(first, RemittanceStatus::Completed));
+        assert_eq!(first, second);
+        
+        // The remittance was only added to the history once
+        let history = RemittanceContractService::get_remittance_history(env.clone(), remittance.sender_account);
+        assert_eq!(history.len(), 1);
+    });
+}
+
+#[test]
+#[should_panic(expected = "Failed to execute remittance")]
+fn test_idempotency_key_expires() {
+    let env = create_test_env();
+    // Keep the contract instance alive past the key's TTL
+    env.ledger().with_mut(|li| {
+        li.min_persistent_entry_ttl = 50_000;
+    });
+    let contract_id = create_test_contract(&env);
+    
+    env.as_contract(&contract_id, || {
+        let remittance = create_test_remittance(&env);
+        let key = Some(BytesN::from_array(&env, &[2; 32]));
+        RemittanceContractService::execute_remittance(env.clone(), remittance.id.clone(), key.clone());
+        
+        // Once the key has expired the call runs again and fails on the completed remittance
+        env.ledger().with_mut(|li| {
+            li.sequence_number += modules::storage::IDEMPOTENCY_TTL + 1;
+        });
+        RemittanceContractService::execute_remittance(env.clone(), remittance.id, key);
+    });
+}
+
 /// Helper function to create a test remittance contract for testing
 fn create_test_remittance(env: &Env) -> RemittanceContract {
     let sender_account = String::from_str(env, "sender123");
@@ -0,0 +1,232 @@
+use crate::types::{
+    DataKey, ReleaseType, VestingError, VestingSchedule, VestingStatus, VestingTerms,
+};
+use soroban_sdk::{contract, contractimpl, token::Client, Address, Env, Symbol, Vec};
+
+#[contract]
+pub struct TokenVesting;
+
+#[contractimpl]
+impl TokenVesting {
+    // Create a vesting schedule and fund it with the full grant from the grantor
+    pub fn create_schedule(
+        env: Env,
+        grantor: Address,
+        beneficiary: Address,
+        terms: VestingTerms,
+    ) -> Result<u64, VestingError> {
+        if terms.total_amount <= 0 {
+            return Err(VestingError::InvalidAmount);
+        }
+        Self::validate_terms(&terms)?;
+
+        grantor.require_auth();
+
+        let schedule_id = Self::get_schedule_count(&env) + 1;
+        let schedule = VestingSchedule {
+            id: schedule_id,
+            grantor: grantor.clone(),
+            beneficiary,
+            terms: terms.clone(),
+            released: 0,
+            status: VestingStatus::Active,
+            created_at: env.ledger().timestamp(),
+        };
+
+        // Transfer the grant from the grantor to the contract
+        Client::new(&env, &terms.token).transfer(
+            &grantor,
+            &env.current_contract_address(),
+            &terms.total_amount,
+        );
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Schedule(schedule_id), &schedule);
+        env.storage()
+            .persistent()
+            .set(&DataKey::ScheduleCount, &schedule_id);
+
+        // Track the schedule under its grantor
+        let grantor_key = DataKey::GrantorSchedules(grantor.clone());
+        let mut grantor_schedules: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&grantor_key)
+            .unwrap_or(Vec::new(&env));
+        grantor_schedules.push_back(schedule_id);
+        env.storage()
+            .persistent()
+            .set(&grantor_key, &grantor_schedules);
+
+        env.events().publish(
+            (
+                Symbol::new(&env, "VestingScheduleCreated"),
+                schedule_id,
+                grantor,
+            ),
+            schedule,
+        );
+
+        Ok(schedule_id)
+    }
+
+    // Pay the beneficiary everything that has vested and not been released yet
+    pub fn release(env: Env, schedule_id: u64) -> Result<i128, VestingError> {
+        let mut schedule = Self::get_schedule_by_id(&env, schedule_id)?;
+
+        if schedule.status != VestingStatus::Active {
+            return Err(VestingError::InvalidStatus);
+        }
+
+        schedule.beneficiary.require_auth();
+
+        let amount = Self::vested(&env, &schedule.terms) - schedule.released;
+        if amount <= 0 {
+            return Err(VestingError::NothingToRelease);
+        }
+
+        schedule.released += amount;
+        if schedule.released == schedule.terms.total_amount {
+            schedule.status = VestingStatus::Completed;
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::Schedule(schedule_id), &schedule);
+
+        Client::new(&env, &schedule.terms.token).transfer(
+            &env.current_contract_address(),
+            &schedule.beneficiary,
+            &amount,
+        );
+
+        env.events().publish(
+            (
+                Symbol::new(&env, "TokensReleased"),
+                schedule_id,
+                schedule.beneficiary.clone(),
+            ),
+            (amount, schedule.released),
+        );
+
+        Ok(amount)
+    }
+
+    // Stop a revocable schedule. What has vested is paid to the beneficiary
+    // and the unvested remainder goes back to the grantor.
+    pub fn revoke(env: Env, schedule_id: u64) -> Result<(), VestingError> {
+        let mut schedule = Self::get_schedule_by_id(&env, schedule_id)?;
+
+        if schedule.status != VestingStatus::Active {
+            return Err(VestingError::InvalidStatus);
+        }
+        if !schedule.terms.revocable {
+            return Err(VestingError::NotRevocable);
+        }
+
+        schedule.grantor.require_auth();
+
+        let vested = Self::vested(&env, &schedule.terms);
+        let beneficiary_amount = vested - schedule.released;
+        let refund = schedule.terms.total_amount - vested;
+
+        schedule.released = vested;
+        schedule.status = VestingStatus::Revoked;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Schedule(schedule_id), &schedule);
+
+        let token_client = Client::new(&env, &schedule.terms.token);
+        if beneficiary_amount > 0 {
+            token_client.transfer(
+                &env.current_contract_address(),
+                &schedule.beneficiary,
+                &beneficiary_amount,
+            );
+        }
+        if refund > 0 {
+            token_client.transfer(&env.current_contract_address(), &schedule.grantor, &refund);
+        }
+
+        env.events().publish(
+            (
+                Symbol::new(&env, "VestingScheduleRevoked"),
+                schedule_id,
+                schedule.grantor,
+            ),
+            (beneficiary_amount, refund),
+        );
+
+        Ok(())
+    }
+
+    // Amount the beneficiary could release right now
+    pub fn releasable(env: Env, schedule_id: u64) -> Result<i128, VestingError> {
+        let schedule = Self::get_schedule_by_id(&env, schedule_id)?;
+        if schedule.status != VestingStatus::Active {
+            return Ok(0);
+        }
+        Ok(Self::vested(&env, &schedule.terms) - schedule.released)
+    }
+
+    pub fn get_schedule(env: Env, schedule_id: u64) -> Result<VestingSchedule, VestingError> {
+        Self::get_schedule_by_id(&env, schedule_id)
+    }
+
+    // IDs of all schedules a grantor has created
+    pub fn get_grantor_schedules(env: Env, grantor: Address) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::GrantorSchedules(grantor))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    // Helper functions
+    fn validate_terms(terms: &VestingTerms) -> Result<(), VestingError> {
+        if terms.start_time >= terms.end_time
+            || terms.cliff_time < terms.start_time
+            || terms.cliff_time > terms.end_time
+        {
+            return Err(VestingError::InvalidSchedule);
+        }
+        // Steps have to divide the vesting period evenly
+        if let ReleaseType::Step(step) = terms.release_type {
+            if step == 0 || !(terms.end_time - terms.start_time).is_multiple_of(step) {
+                return Err(VestingError::InvalidSchedule);
+            }
+        }
+        Ok(())
+    }
+
+    // Total amount vested at the current ledger time
+    fn vested(env: &Env, terms: &VestingTerms) -> i128 {
+        let now = env.ledger().timestamp();
+        if now < terms.cliff_time {
+            return 0;
+        }
+        if now >= terms.end_time {
+            return terms.total_amount;
+        }
+
+        let duration = terms.end_time - terms.start_time;
+        let elapsed = match terms.release_type {
+            ReleaseType::Linear => now - terms.start_time,
+            ReleaseType::Step(step) => (now - terms.start_time) / step * step,
+        };
+        terms.total_amount * elapsed as i128 / duration as i128
+    }
+
+    fn get_schedule_count(env: &Env) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ScheduleCount)
+            .unwrap_or(0)
+    }
+
+    fn get_schedule_by_id(env: &Env, schedule_id: u64) -> Result<VestingSchedule, VestingError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Schedule(schedule_id))
+            .ok_or(VestingError::ScheduleNotFound)
+    }
+}
@@ -0,0 +1,12 @@
+#![no_std]
+
+mod contract;
+mod types;
+
+pub use contract::*;
+
+#[cfg(test)]
+mod test;
+
+#[cfg(test)]
+mod testutils;
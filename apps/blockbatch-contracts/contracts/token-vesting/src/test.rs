@@ -0,0 +1,232 @@
+use super::*;
+use crate::testutils::{check_balance, create_token_contract, mint_tokens};
+use crate::types::*;
+use soroban_sdk::testutils::{Address as _, Events, Ledger};
+use soroban_sdk::{Address, Env};
+
+#[cfg(test)]
+mod test_setup {
+    use super::*;
+
+    pub const START: u64 = 1_000;
+    pub const CLIFF: u64 = 1_250;
+    pub const END: u64 = 2_000;
+
+    pub fn setup_contract(e: &Env) -> (TokenVestingClient<'_>, Address, Address, Address) {
+        let admin = Address::generate(e);
+        let grantor = Address::generate(e);
+        let beneficiary = Address::generate(e);
+
+        let (token, token_admin) = create_token_contract(e, &admin);
+
+        let contract_id = e.register(TokenVesting, ());
+        let client = TokenVestingClient::new(e, &contract_id);
+
+        e.mock_all_auths();
+
+        mint_tokens(&token_admin, &grantor, &100_000);
+
+        (client, grantor, beneficiary, token)
+    }
+
+    // A grant of 10_000 vesting from START to END with a cliff at CLIFF
+    pub fn terms(token: &Address, release_type: ReleaseType, revocable: bool) -> VestingTerms {
+        VestingTerms {
+            token: token.clone(),
+            total_amount: 10_000,
+            start_time: START,
+            cliff_time: CLIFF,
+            end_time: END,
+            release_type,
+            revocable,
+        }
+    }
+
+    pub fn set_time(e: &Env, timestamp: u64) {
+        e.ledger().with_mut(|li| {
+            li.timestamp = timestamp;
+        });
+    }
+}
+
+mod test_create {
+    use super::*;
+
+    #[test]
+    fn test_grantor_holds_many_schedules() {
+        let env = Env::default();
+        let (client, grantor, beneficiary, token) = test_setup::setup_contract(&env);
+
+        let first = client.create_schedule(
+            &grantor,
+            &beneficiary,
+            &test_setup::terms(&token, ReleaseType::Linear, false),
+        );
+        let second = client.create_schedule(
+            &grantor,
+            &Address::generate(&env),
+            &test_setup::terms(&token, ReleaseType::Step(250), true),
+        );
+
+        let schedules = client.get_grantor_schedules(&grantor);
+        assert_eq!(schedules.len(), 2);
+        assert_eq!(schedules.get(0), Some(first));
+        assert_eq!(schedules.get(1), Some(second));
+        assert_eq!(check_balance(&env, &token, &client.address), 20_000);
+    }
+
+    #[test]
+    fn test_create_schedule_emits_event() {
+        let env = Env::default();
+        let (client, grantor, beneficiary, token) = test_setup::setup_contract(&env);
+        client.create_schedule(
+            &grantor,
+            &beneficiary,
+            &test_setup::terms(&token, ReleaseType::Linear, false),
+        );
+        assert!(!env.events().all().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_create_schedule_cliff_after_end() {
+        let env = Env::default();
+        let (client, grantor, beneficiary, token) = test_setup::setup_contract(&env);
+        let mut terms = test_setup::terms(&token, ReleaseType::Linear, false);
+        terms.cliff_time = test_setup::END + 1;
+        client.create_schedule(&grantor, &beneficiary, &terms);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_create_schedule_uneven_steps() {
+        let env = Env::default();
+        let (client, grantor, beneficiary, token) = test_setup::setup_contract(&env);
+        client.create_schedule(
+            &grantor,
+            &beneficiary,
+            &test_setup::terms(&token, ReleaseType::Step(300), false),
+        );
+    }
+}
+
+mod test_release {
+    use super::*;
+
+    #[test]
+    fn test_nothing_releasable_before_cliff() {
+        let env = Env::default();
+        let (client, grantor, beneficiary, token) = test_setup::setup_contract(&env);
+        let schedule_id = client.create_schedule(
+            &grantor,
+            &beneficiary,
+            &test_setup::terms(&token, ReleaseType::Linear, false),
+        );
+
+        test_setup::set_time(&env, test_setup::CLIFF - 1);
+        assert_eq!(client.releasable(&schedule_id), 0);
+
+        // At the cliff everything vested since the start becomes releasable
+        test_setup::set_time(&env, test_setup::CLIFF);
+        assert_eq!(client.releasable(&schedule_id), 2_500);
+    }
+
+    #[test]
+    fn test_linear_release() {
+        let env = Env::default();
+        let (client, grantor, beneficiary, token) = test_setup::setup_contract(&env);
+        let schedule_id = client.create_schedule(
+            &grantor,
+            &beneficiary,
+            &test_setup::terms(&token, ReleaseType::Linear, false),
+        );
+
+        test_setup::set_time(&env, 1_300);
+        assert_eq!(client.release(&schedule_id), 3_000);
+        test_setup::set_time(&env, 1_550);
+        assert_eq!(client.release(&schedule_id), 2_500);
+        test_setup::set_time(&env, test_setup::END);
+        assert_eq!(client.release(&schedule_id), 4_500);
+
+        assert_eq!(check_balance(&env, &token, &beneficiary), 10_000);
+        assert_eq!(
+            client.get_schedule(&schedule_id).status,
+            VestingStatus::Completed
+        );
+    }
+
+    #[test]
+    fn test_step_release() {
+        let env = Env::default();
+        let (client, grantor, beneficiary, token) = test_setup::setup_contract(&env);
+        let schedule_id = client.create_schedule(
+            &grantor,
+            &beneficiary,
+            &test_setup::terms(&token, ReleaseType::Step(250), false),
+        );
+
+        // Four steps of 2_500; the second one completes at 1_500
+        test_setup::set_time(&env, 1_499);
+        assert_eq!(client.releasable(&schedule_id), 2_500);
+        test_setup::set_time(&env, 1_500);
+        assert_eq!(client.releasable(&schedule_id), 5_000);
+        assert_eq!(client.release(&schedule_id), 5_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_release_twice_same_time() {
+        let env = Env::default();
+        let (client, grantor, beneficiary, token) = test_setup::setup_contract(&env);
+        let schedule_id = client.create_schedule(
+            &grantor,
+            &beneficiary,
+            &test_setup::terms(&token, ReleaseType::Step(250), false),
+        );
+        test_setup::set_time(&env, 1_600);
+        client.release(&schedule_id);
+        client.release(&schedule_id);
+    }
+}
+
+mod test_revoke {
+    use super::*;
+
+    #[test]
+    fn test_revoke_pays_vested_and_refunds_rest() {
+        let env = Env::default();
+        let (client, grantor, beneficiary, token) = test_setup::setup_contract(&env);
+        let schedule_id = client.create_schedule(
+            &grantor,
+            &beneficiary,
+            &test_setup::terms(&token, ReleaseType::Linear, true),
+        );
+
+        test_setup::set_time(&env, 1_300);
+        client.release(&schedule_id);
+        test_setup::set_time(&env, 1_400);
+        client.revoke(&schedule_id);
+
+        assert_eq!(check_balance(&env, &token, &beneficiary), 4_000);
+        assert_eq!(check_balance(&env, &token, &grantor), 96_000);
+        assert_eq!(check_balance(&env, &token, &client.address), 0);
+        assert_eq!(
+            client.get_schedule(&schedule_id).status,
+            VestingStatus::Revoked
+        );
+        assert_eq!(client.releasable(&schedule_id), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #6)")]
+    fn test_revoke_irrevocable_schedule() {
+        let env = Env::default();
+        let (client, grantor, beneficiary, token) = test_setup::setup_contract(&env);
+        let schedule_id = client.create_schedule(
+            &grantor,
+            &beneficiary,
+            &test_setup::terms(&token, ReleaseType::Linear, false),
+        );
+        client.revoke(&schedule_id);
+    }
+}
@@ -0,0 +1,60 @@
+use soroban_sdk::{contracterror, contracttype, Address};
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReleaseType {
+    Linear,    // Vests continuously between start and end
+    Step(u64), // Vests in equal steps of the given length in seconds
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VestingStatus {
+    Active,
+    Completed,
+    Revoked,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VestingTerms {
+    pub token: Address,
+    pub total_amount: i128,
+    pub start_time: u64,
+    pub cliff_time: u64, // Nothing can be released before this time
+    pub end_time: u64,
+    pub release_type: ReleaseType,
+    pub revocable: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VestingSchedule {
+    pub id: u64,
+    pub grantor: Address,
+    pub beneficiary: Address,
+    pub terms: VestingTerms,
+    pub released: i128, // Paid out to the beneficiary so far
+    pub status: VestingStatus,
+    pub created_at: u64,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum VestingError {
+    Unauthorized = 1,
+    InvalidAmount = 2,
+    InvalidSchedule = 3,
+    ScheduleNotFound = 4,
+    InvalidStatus = 5,
+    NotRevocable = 6,
+    NothingToRelease = 7,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    ScheduleCount,             // Number of schedules created so far
+    Schedule(u64),             // Schedule details by ID
+    GrantorSchedules(Address), // IDs of the schedules funded by a grantor
+}